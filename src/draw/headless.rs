@@ -0,0 +1,45 @@
+use draw::traits::*;
+use game::{QuitStatus, TankControls};
+use std::collections::HashMap;
+
+const VIEWPORT_SIZE: (u32, u32) = (1024, 768);
+
+// Runs matches without opening a display or GL context. Nothing is drawn,
+// and the match is abandoned once the optional frame limit is reached.
+pub struct HeadlessWindow {
+    frame_limit: Option<u32>,
+    frames: u32,
+}
+
+impl HeadlessWindow {
+    pub fn new(frame_limit: Option<u32>) -> HeadlessWindow {
+        HeadlessWindow {
+            frame_limit: frame_limit,
+            frames: 0,
+        }
+    }
+}
+
+impl Window for HeadlessWindow {
+    fn swap_window(&mut self) {
+        self.frames += 1;
+    }
+    fn set_controls(&mut self, controls: HashMap<u8, TankControls>) {
+        if !controls.is_empty() {
+            warn!("Headless window has no input, human controls will never be used");
+        }
+    }
+    fn set_active_player(&mut self, _: Option<u8>) {}
+    fn get_viewport_size(&mut self) -> (u32, u32) {
+        VIEWPORT_SIZE
+    }
+    fn poll_events(&mut self) -> Option<QuitStatus> {
+        match self.frame_limit {
+            Some(limit) if self.frames >= limit => {
+                warn!("Headless match did not finish within {} frames", limit);
+                Some(QuitStatus::Quit)
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use gfx;
 
+mod headless;
 mod renderer;
 mod traits;
 mod system;
@@ -8,6 +9,7 @@ mod window;
 pub type ColorFormat = gfx::format::Rgba8;
 pub type DepthFormat = gfx::format::Depth;
 
+pub use self::headless::HeadlessWindow;
 pub use self::renderer::{DeviceRenderer, EncoderQueue};
 pub use self::system::DrawSystem;
 pub use self::traits::*;
@@ -1,7 +1,9 @@
 use draw::{ColorFormat, EncoderQueue};
 use explosion;
 use gfx;
+use physics::Tick;
 use projectile;
+use shield;
 use specs;
 use tank;
 use terrain;
@@ -13,6 +15,7 @@ pub struct DrawSystem<D: gfx::Device> {
     terrain_system: terrain::DrawSystem<D::Resources>,
     projectile_system: projectile::DrawSystem<D::Resources>,
     explosion_system: explosion::DrawSystem<D::Resources>,
+    shield_system: shield::DrawSystem<D::Resources>,
     text_system: text::DrawSystem<D::Resources>,
     encoder_queue: EncoderQueue<D>,
 }
@@ -31,25 +34,31 @@ impl<D: gfx::Device> DrawSystem<D> {
             terrain_system: terrain::DrawSystem::new(factory, rtv.clone(), terrain),
             projectile_system: projectile::DrawSystem::new(factory, rtv.clone()),
             explosion_system: explosion::DrawSystem::new(factory, rtv.clone()),
+            shield_system: shield::DrawSystem::new(factory, rtv.clone()),
             text_system: text::DrawSystem::new(factory, rtv.clone()),
             encoder_queue: queue,
         }
     }
 }
 
-impl<D, C> specs::System<C> for DrawSystem<D>
+impl<D> specs::System<Tick> for DrawSystem<D>
     where D: gfx::Device,
           D::CommandBuffer: Send
 {
-    fn run(&mut self, arg: specs::RunArg, _: C) {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
         use specs::Join;
+        if !tick.render {
+            arg.fetch(|_| ());
+            return;
+        }
         let mut encoder = self.encoder_queue.receiver.recv().unwrap();
-        let (tanks, terrain, projectiles, explosives, texts) =
+        let (tanks, terrain, projectiles, explosives, shields, texts) =
             arg.fetch(|w| {
                           (w.read::<tank::Drawable>(),
                            w.read::<terrain::Drawable>(),
                            w.read::<projectile::Drawable>(),
                            w.read::<explosion::Drawable>(),
+                           w.read::<shield::Drawable>(),
                            w.read::<text::Drawable>())
                       });
 
@@ -67,6 +76,10 @@ impl<D, C> specs::System<C> for DrawSystem<D>
         for e in (&explosives).join() {
             self.explosion_system.draw(e, &mut encoder);
         }
+        // Drawn over everything else in the world, since they are see-through
+        for s in (&shields).join() {
+            self.shield_system.draw(s, &mut encoder);
+        }
         for e in (&texts).join() {
             self.text_system.draw(e, &mut encoder);
         }
@@ -1,15 +1,22 @@
 use draw::ColorFormat;
 use game::{QuitStatus, TankControls};
 use gfx;
+use std::collections::HashMap;
 
-pub trait Window<D: gfx::Device, F: gfx::Factory<D::Resources>> {
+pub trait Window {
     fn swap_window(&mut self);
     fn poll_events(&mut self) -> Option<QuitStatus>;
 
-    fn create_buffers(&mut self, count: usize) -> Vec<D::CommandBuffer>;
-    fn set_controls(&mut self, controls: TankControls);
+    // Controls for every human player, keyed by player number
+    fn set_controls(&mut self, controls: HashMap<u8, TankControls>);
+    fn set_active_player(&mut self, player: Option<u8>);
 
     fn get_viewport_size(&mut self) -> (u32, u32);
+}
+
+pub trait GfxWindow<D: gfx::Device, F: gfx::Factory<D::Resources>>: Window {
+    fn create_buffers(&mut self, count: usize) -> Vec<D::CommandBuffer>;
+
     fn get_device(&mut self) -> &mut D;
     fn get_factory(&mut self) -> &mut F;
     fn get_rtv(&mut self) -> gfx::handle::RenderTargetView<D::Resources, ColorFormat>;
@@ -5,6 +5,7 @@ use gfx;
 use gfx_device_gl;
 use gfx_window_glutin;
 use glutin;
+use std::collections::HashMap;
 
 pub struct GlutinWindow {
     window: glutin::Window,
@@ -12,7 +13,8 @@ pub struct GlutinWindow {
     factory: gfx_device_gl::Factory,
     rtv: gfx::handle::RenderTargetView<gfx_device_gl::Resources, ColorFormat>,
     _dsv: gfx::handle::DepthStencilView<gfx_device_gl::Resources, DepthFormat>,
-    controls: Option<TankControls>,
+    controls: HashMap<u8, TankControls>,
+    active_player: Option<u8>,
 }
 
 impl GlutinWindow {
@@ -34,19 +36,13 @@ impl GlutinWindow {
             factory: factory,
             rtv: rtv,
             _dsv: _dsv,
-            controls: None,
+            controls: HashMap::new(),
+            active_player: None,
         }
     }
 }
 
-impl Window<gfx_device_gl::Device, gfx_device_gl::Factory> for GlutinWindow {
-    fn swap_window(&mut self) {
-        use gfx::Device;
-        self.window
-            .swap_buffers()
-            .expect("Unable to swap buffers");
-        self.device.cleanup();
-    }
+impl GfxWindow<gfx_device_gl::Device, gfx_device_gl::Factory> for GlutinWindow {
     fn create_buffers(&mut self, count: usize) -> Vec<gfx_device_gl::CommandBuffer> {
         let mut bufs = Vec::new();
         for _ in 0..count {
@@ -54,14 +50,6 @@ impl Window<gfx_device_gl::Device, gfx_device_gl::Factory> for GlutinWindow {
         }
         bufs
     }
-    fn set_controls(&mut self, controls: TankControls) {
-        self.controls = Some(controls)
-    }
-    fn get_viewport_size(&mut self) -> (u32, u32) {
-        self.window
-            .get_inner_size_pixels()
-            .unwrap_or((1024, 768))
-    }
     fn get_device(&mut self) -> &mut gfx_device_gl::Device {
         &mut self.device
     }
@@ -71,20 +59,58 @@ impl Window<gfx_device_gl::Device, gfx_device_gl::Factory> for GlutinWindow {
     fn get_rtv(&mut self) -> gfx::handle::RenderTargetView<gfx_device_gl::Resources, ColorFormat> {
         self.rtv.clone()
     }
+}
+
+impl Window for GlutinWindow {
+    fn swap_window(&mut self) {
+        use gfx::Device;
+        self.window
+            .swap_buffers()
+            .expect("Unable to swap buffers");
+        self.device.cleanup();
+    }
+    fn set_controls(&mut self, controls: HashMap<u8, TankControls>) {
+        self.controls = controls;
+        self.active_player = None;
+    }
+    fn set_active_player(&mut self, player: Option<u8>) {
+        if player == self.active_player {
+            return;
+        }
+        // Release any keys still held by the previous player
+        let controls = &mut self.controls;
+        if let Some(previous) = self.active_player.and_then(|p| controls.get_mut(&p)) {
+            previous.angle_stop();
+            previous.power_stop();
+            previous.move_stop();
+        }
+        self.active_player = player;
+    }
+    fn get_viewport_size(&mut self) -> (u32, u32) {
+        self.window
+            .get_inner_size_pixels()
+            .unwrap_or((1024, 768))
+    }
     fn poll_events(&mut self) -> Option<QuitStatus> {
         use glutin::Event::*;
         use glutin::VirtualKeyCode::*;
         use glutin::ElementState::*;
 
-        let controls = match self.controls {
-            Some(ref mut c) => c,
-            None => panic!("Controls have not been initialized"),
-        };
-
         for event in self.window.poll_events() {
-            match event {
+            let controls = match event {
                 Closed |
                 KeyboardInput(_, _, Some(Escape)) => return Some(QuitStatus::Quit),
+                KeyboardInput(..) => {
+                    // Only the human whose turn it is can use the keyboard
+                    let controls = &mut self.controls;
+                    match self.active_player.and_then(|p| controls.get_mut(&p)) {
+                        Some(c) => c,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            match event {
                 KeyboardInput(Pressed, _, Some(Space)) => controls.fire(),
                 KeyboardInput(Pressed, _, Some(Left)) => controls.angle_decrease(),
                 KeyboardInput(Pressed, _, Some(Right)) => controls.angle_increase(),
@@ -94,6 +120,12 @@ impl Window<gfx_device_gl::Device, gfx_device_gl::Factory> for GlutinWindow {
                 KeyboardInput(Pressed, _, Some(Down)) => controls.power_decrease(),
                 KeyboardInput(Released, _, Some(Up)) |
                 KeyboardInput(Released, _, Some(Down)) => controls.power_stop(),
+                KeyboardInput(Pressed, _, Some(PageDown)) => controls.next_weapon(),
+                KeyboardInput(Pressed, _, Some(PageUp)) => controls.previous_weapon(),
+                KeyboardInput(Pressed, _, Some(A)) => controls.move_left(),
+                KeyboardInput(Pressed, _, Some(D)) => controls.move_right(),
+                KeyboardInput(Released, _, Some(A)) |
+                KeyboardInput(Released, _, Some(D)) => controls.move_stop(),
                 _ => (),
             }
         }
@@ -1,8 +1,13 @@
 use cgmath::Deg;
 use cgmath::prelude::*;
+use game::{Players, Shot, Statistics, label_text};
 use physics::*;
+use projectile::{Falloff, Weapon};
+use shield::Shield;
 use specs::{self, Join};
-use tank::Tank;
+use tank::{Effect, Status, Tank};
+use terrain::Terrain;
+use text::Text;
 
 mod draw;
 
@@ -12,18 +17,34 @@ pub use self::draw::{Drawable, DrawSystem, PreDrawSystem};
 pub struct Explosion {
     time_elapsed: f32,
     time_remaining: f32,
+    duration: f32,
+    // Dealt at the centre of the blast, less further out according to the falloff
+    damage: f32,
+    falloff: Falloff,
+    effect: Option<(Effect, u32)>,
+    // Tanks already damaged, so the blast only hits each of them once
+    hit: Vec<specs::Entity>,
+    // Who fired the shell, to credit them with the damage
+    shot: Option<Shot>,
 }
 
 impl Explosion {
-    pub fn new() -> Explosion {
+    pub fn new(weapon: &Weapon, shot: Option<Shot>) -> Explosion {
         Explosion {
             time_elapsed: 0.0,
-            time_remaining: 5.0,
+            time_remaining: weapon.explosion_duration,
+            duration: weapon.explosion_duration,
+            damage: weapon.damage,
+            falloff: weapon.falloff,
+            effect: weapon.effect,
+            hit: Vec::new(),
+            shot: shot,
         }
     }
 
+    // Grows to 1.0 and shrinks back a little before the explosion ends
     pub fn radius(&self) -> f32 {
-        Deg(25.0 * self.time_elapsed).sin()
+        Deg(125.0 * self.time_elapsed / self.duration).sin()
     }
 }
 
@@ -40,40 +61,94 @@ impl ExplosionSystem {
     }
 }
 
-impl specs::System<f32> for ExplosionSystem {
-    fn run(&mut self, args: specs::RunArg, time: f32) {
-        let (mut explosions, positions, mut tanks, entities) =
-            args.fetch(|w| {
+impl specs::System<Tick> for ExplosionSystem {
+    fn run(&mut self, args: specs::RunArg, tick: Tick) {
+        let time = tick.delta;
+        let (mut explosions,
+             positions,
+             mut tanks,
+             mut statuses,
+             mut shields,
+             mut texts,
+             entities,
+             mut terrain,
+             players,
+             mut stats) = args.fetch(|w| {
                            (w.write::<Explosion>(),
                             w.read::<Position>(),
                             w.write::<Tank>(),
-                            w.entities())
+                            w.write::<Status>(),
+                            w.write::<Shield>(),
+                            w.write::<Text>(),
+                            w.entities(),
+                            w.write_resource::<Terrain>(),
+                            w.read_resource::<Players>(),
+                            w.write_resource::<Statistics>())
                        });
-        let mut damage_areas = Vec::new();
+        let targets: Vec<(specs::Entity, Position)> = (&tanks.check(), &positions, &entities)
+            .join()
+            .map(|(_, p, id)| (id, p.clone()))
+            .collect();
+        let mut damage = Vec::new();
         for (e, p, id) in (&mut explosions, &positions, &entities).join() {
             e.time_elapsed += time;
             e.time_remaining -= time;
             trace!("Explosion running for {}, has {} remaining",
                    e.time_elapsed,
                    e.time_remaining);
-            let mut damage_time = time;
             if e.time_remaining <= 0.0 {
                 args.delete(id);
-                damage_time += e.time_remaining;
+                // The radius peaks at 1.0, which is scaled up to the explosion size in the world
+                terrain.carve(p.position, p.scale);
             }
-            if damage_time > 0.0 {
-                damage_areas.push((p.position, e.radius(), damage_time * 11.0));
+            // Tanks take their damage once, as soon as the growing blast reaches them
+            let radius = e.radius() * p.scale;
+            for &(tank, ref t) in &targets {
+                let distance = p.position.distance(t.position);
+                if e.hit.contains(&tank) || distance > radius + t.scale {
+                    continue;
+                }
+                e.hit.push(tank);
+                let reach = p.scale + t.scale;
+                let amount = e.damage * e.falloff.scale(distance / reach);
+                damage.push((tank, e.shot, amount, e.effect));
             }
         }
-        for (t, p, id) in (&mut tanks, &positions, &entities).join() {
-            for &(center, radius, damage) in &damage_areas {
-                if p.position.distance(center) <= (radius + p.scale) {
-                    t.health -= damage;
-                    debug!("Tank health: {}", t.health);
-                    if t.health <= 0.0 {
-                        info!("Tank destroyed!");
-                        args.delete(id);
-                    }
+        for (tank, shot, amount, effect) in damage {
+            if let Some(t) = tanks.get_mut(tank) {
+                if t.health <= 0.0 {
+                    continue;
+                }
+                // Whatever the shield can't soak up goes through to the tank
+                let mut amount = amount * statuses.get(tank).map_or(1.0, |s| s.damage_scale());
+                if let Some(s) = shields.get_mut(tank) {
+                    amount = s.absorb(amount);
+                    debug!("Shield absorbed an explosion, strength: {}", s.strength);
+                }
+                let dealt = t.take_damage(amount);
+                debug!("Tank took {} damage from an explosion, health: {}", amount, t.health);
+                let destroyed = t.health <= 0.0;
+                if destroyed {
+                    info!("Tank destroyed!");
+                    args.delete(tank);
+                }
+                let target = match players.by_tank(tank) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                stats.shell_damage(shot, &target, dealt, destroyed);
+                if destroyed {
+                    continue;
+                }
+                if let (Some((effect, turns)), Some(s)) = (effect, statuses.get_mut(tank)) {
+                    debug!("Tank {} is {} for {} turns",
+                           target.player_number(),
+                           effect.name(),
+                           turns);
+                    s.apply(effect, turns, shot.map(|shot| shot.player));
+                }
+                if let Some(text) = texts.get_mut(tank) {
+                    text.text = label_text(players.name(&target), t, statuses.get(tank));
                 }
             }
         }
@@ -1,61 +1,247 @@
-use cgmath::{Deg, Point2};
+use cgmath::{Deg, Point2, Vector2};
 use cgmath::prelude::*;
-use game::{ActivePlayer, Player, TankControls};
-use physics::{GRAVITY, Position};
-use projectile::{POWER_MIN, POWER_SCALE};
+use game::{ActivePlayer, Difficulty, GameRng, GameSeed, Loadout, Player, Players, Statistics,
+           TankControls, Targeting};
+use game::shop::{self, Goods};
+use physics::{GRAVITY, Position, TICK_DELTA, Tick, Wind, apply_drag};
+use projectile::{Arsenal, POWER_MIN, POWER_SCALE, Weapon, WeaponKind};
+use rand::Rng;
 use specs::{self, Join};
-use tank::Tank;
+use std::cmp::Ordering;
+use std::f32;
+use tank::{Effect, Status, Tank};
+
+// Shells still in the air after this long are assumed to never reach the target
+const MAX_FLIGHT_TICKS: u32 = 1200;
+// Weapons the computer can aim, best first
+const PREFERRED_WEAPONS: [WeaponKind; 4] = [WeaponKind::Nuke,
+                                            WeaponKind::Mirv,
+                                            WeaponKind::Missile,
+                                            WeaponKind::BabyMissile];
+// What the computer buys, in order, as long as the money lasts
+const SHOPPING_LIST: [Goods; 4] = [Goods::Shield,
+                                   Goods::Shells(WeaponKind::Nuke),
+                                   Goods::Parachute,
+                                   Goods::Shells(WeaponKind::Mirv)];
+
+// Keeps a shield and a parachute, and spends the rest on the heaviest shells it can afford.
+// Easier computer players save half their money instead of spending it.
+pub fn choose_purchases(loadout: &mut Loadout, difficulty: Difficulty) {
+    let savings = match difficulty {
+        Difficulty::Easy => loadout.money / 2,
+        _ => 0,
+    };
+    let mut bought = true;
+    while bought {
+        bought = false;
+        for &goods in &SHOPPING_LIST {
+            let offer = shop::offer(goods);
+            let wanted = match goods {
+                Goods::Shield | Goods::Parachute => loadout.owned(goods) == 0,
+                Goods::Shells(_) => true,
+            };
+            if wanted && loadout.money >= offer.price + savings && loadout.buy(offer) {
+                debug!("Computer bought {} {}", offer.quantity, goods.name());
+                bought = true;
+            }
+        }
+    }
+}
+
+fn preferred_weapon(arsenal: &Arsenal) -> WeaponKind {
+    PREFERRED_WEAPONS
+        .iter()
+        .cloned()
+        .find(|&w| arsenal.has(w))
+        .unwrap_or(WeaponKind::Missile)
+}
+
+// How well a computer player aims, set by its difficulty
+#[derive(Debug,Copy,Clone)]
+struct Skill {
+    // Seconds spent at the start of each turn before doing anything
+    thinking_time: f32,
+    // Adjustments made while aiming, after which it fires with whatever aim it has
+    max_adjustments: u32,
+    // Once aimed, the angle and power are knocked off by up to this many steps either way
+    angle_error: i32,
+    power_error: i32,
+}
+
+impl Skill {
+    fn new(difficulty: Difficulty) -> Skill {
+        let (thinking_time, max_adjustments, angle_error, power_error) = match difficulty {
+            Difficulty::Easy => (2.0, 150, 8, 2),
+            Difficulty::Normal => (1.0, 300, 4, 1),
+            Difficulty::Hard => (0.5, 600, 2, 0),
+            Difficulty::Expert => (0.0, 2000, 0, 0),
+        };
+        Skill {
+            thinking_time: thinking_time,
+            max_adjustments: max_adjustments,
+            angle_error: angle_error,
+            power_error: power_error,
+        }
+    }
+}
+
+#[derive(Debug,Copy,Clone)]
+struct Target {
+    player: Player,
+    position: Point2<f32>,
+    health: f32,
+    distance: f32,
+}
+
+fn compare(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
 
 #[derive(Debug)]
 enum TargetingState {
     WaitForTurn,
+    Think(f32),
     SelectTarget,
     AlignToTarget(Point2<f32>),
+    // Steps of angle and power still to be put off
+    Miss(i32, i32),
     Fire,
 }
 
-#[derive(Debug)]
 pub struct AiController {
     player: Player,
     controls: TankControls,
     state: TargetingState,
+    skill: Skill,
+    targeting: Targeting,
+    adjustments: u32,
+    // Kept apart from the match's GameRng, so that the computer's mistakes can't change the
+    // battlefield for anyone else
+    rng: GameRng,
 }
 
 impl AiController {
-    pub fn new(player: Player, controls: TankControls) -> AiController {
+    pub fn new(player: Player,
+               controls: TankControls,
+               difficulty: Difficulty,
+               targeting: Targeting,
+               seed: GameSeed)
+               -> AiController {
+        let seed = GameSeed(seed.0 ^ ((player.player_number() as u64) << 56));
         AiController {
             player: player,
             controls: controls,
             state: TargetingState::WaitForTurn,
+            skill: Skill::new(difficulty),
+            targeting: targeting,
+            adjustments: 0,
+            rng: GameRng::new(seed),
         }
     }
 
     fn wait_for_turn(&mut self, args: specs::RunArg) {
         let (tanks, active) = args.fetch(|w| (w.read::<Tank>(), w.read_resource::<ActivePlayer>()));
         if active.player() == Some(self.player) && tanks.get(self.player.id()).is_some() {
-            self.state = TargetingState::SelectTarget;
+            self.state = TargetingState::Think(self.skill.thinking_time);
         }
     }
 
+    fn think(&mut self, args: specs::RunArg, remaining: f32, delta: f32) {
+        args.fetch(|_| ());
+        self.state = if remaining > delta {
+            TargetingState::Think(remaining - delta)
+        } else {
+            TargetingState::SelectTarget
+        };
+    }
+
+    // Picks afresh every turn, since tanks move, get weaker and fire back
     fn select_target(&mut self, args: specs::RunArg) {
-        let (tanks, positions, entities) =
-            args.fetch(|w| (w.read::<Tank>(), w.read::<Position>(), w.entities()));
-        if tanks.get(self.player.id()).is_some() {
-            // Find a tank to target. For now this is simply the first tank we can find.
-            for (_, p, e) in (&tanks.check(), &positions, &entities).join() {
-                if self.player.id() == e {
-                    continue;
-                }
-                debug!("Picking target {:?}", e);
-                self.state = TargetingState::AlignToTarget(p.position);
+        let (tanks, positions, players, stats, entities) = args.fetch(|w| {
+            (w.read::<Tank>(),
+             w.read::<Position>(),
+             w.read_resource::<Players>(),
+             w.read_resource::<Statistics>(),
+             w.entities())
+        });
+        let me = self.player.id();
+        let own = match (tanks.get(me), positions.get(me)) {
+            (Some(_), Some(p)) => p.position,
+            _ => {
+                self.state = TargetingState::WaitForTurn;
+                return;
             }
+        };
+        let targets: Vec<Target> = (&tanks, &positions, &entities)
+            .join()
+            .filter(|&(_, _, e)| e != me)
+            .filter_map(|(t, p, e)| {
+                players
+                    .by_tank(e)
+                    .map(|player| {
+                             Target {
+                                 player: player,
+                                 position: p.position,
+                                 health: t.health,
+                                 distance: own.distance(p.position),
+                             }
+                         })
+            })
+            .collect();
+        if targets.is_empty() {
             return;
         }
-        self.state = TargetingState::WaitForTurn;
+        let target = self.pick_target(&targets, &stats);
+        debug!("Player {} is targeting player {} ({})",
+               self.player.player_number(),
+               target.player.player_number(),
+               self.targeting.name());
+        self.state = TargetingState::AlignToTarget(target.position);
+        self.adjustments = 0;
+    }
+
+    // Falls back on the nearest tank when the strategy has nobody in particular to go for
+    fn pick_target(&mut self, targets: &[Target], stats: &Statistics) -> Target {
+        let nearest = *targets
+                           .iter()
+                           .min_by(|a, b| compare(a.distance, b.distance))
+                           .unwrap();
+        match self.targeting {
+            Targeting::Nearest => nearest,
+            Targeting::Weakest => {
+                *targets
+                     .iter()
+                     .min_by(|a, b| compare(a.health, b.health))
+                     .unwrap()
+            }
+            Targeting::Threat => {
+                let threat = *targets
+                                  .iter()
+                                  .max_by(|a, b| {
+                                              compare(stats.damage_dealt(&a.player),
+                                                      stats.damage_dealt(&b.player))
+                                          })
+                                  .unwrap();
+                if stats.damage_dealt(&threat.player) > 0.0 {
+                    threat
+                } else {
+                    nearest
+                }
+            }
+            Targeting::Revenge => {
+                stats
+                    .last_attacker(&self.player)
+                    .and_then(|attacker| targets.iter().find(|t| t.player == attacker).cloned())
+                    .unwrap_or(nearest)
+            }
+            Targeting::Random => targets[self.rng.gen_range(0, targets.len())],
+        }
     }
 
     fn align_to_target(&mut self, args: specs::RunArg, target: Point2<f32>) {
-        let (tanks, positions) = args.fetch(|w| (w.read::<Tank>(), w.read::<Position>()));
+        let (tanks, statuses, positions, wind) = args.fetch(|w| {
+            (w.read::<Tank>(), w.read::<Status>(), w.read::<Position>(), w.read_resource::<Wind>())
+        });
         let ai_tank = match tanks.get(self.player.id()) {
             Some(t) => t,
             None => return, // This tank doesn't exist anymore...
@@ -65,7 +251,31 @@ impl AiController {
             None => return, // This tank doesn't exist anymore...
         };
 
+        // Nothing can be changed while jammed, so make do with the current aim
+        if statuses.get(self.player.id()).map_or(false, |s| s.has(Effect::Jammed)) {
+            self.state = TargetingState::Fire;
+            return;
+        }
+        if self.adjustments >= self.skill.max_adjustments {
+            debug!("Player {} gave up aiming", self.player.player_number());
+            self.controls.angle_stop();
+            self.controls.power_stop();
+            self.miss();
+            return;
+        }
+        self.adjustments += 1;
+
+        // Switch to the best weapon first, so the aim is worked out for it
+        if ai_tank.weapon != preferred_weapon(&ai_tank.arsenal) {
+            self.controls.next_weapon();
+            return;
+        }
+
         let distance = target - ai_position.position;
+        let flight = Flight {
+            weapon: ai_tank.weapon.weapon(),
+            wind: *wind,
+        };
         trace!("Aiming for {:?}", distance);
 
         if ai_tank.barrel_orient <= Deg::zero() && distance.x > 0.0 {
@@ -79,7 +289,7 @@ impl AiController {
         }
 
         let (y_end, peaked) =
-            calc_end_y(distance.x, ai_tank.power_level, ai_tank.barrel_orient, 75.0);
+            flight.calc_end_y(distance.x, ai_tank.power_level, ai_tank.barrel_orient);
         let y_diff = y_end - distance.y;
 
         trace!("y_diff: {}, peaked? {}", y_diff, peaked);
@@ -97,18 +307,16 @@ impl AiController {
 
         let mut changed = false;
 
-        let (y2, p2) = calc_end_y(distance.x,
-                                  ai_tank.power_level + 0.05,
-                                  ai_tank.barrel_orient,
-                                  75.0);
+        let (y2, p2) = flight.calc_end_y(distance.x,
+                                         ai_tank.power_level + 0.05,
+                                         ai_tank.barrel_orient);
         if p2 && (y2 - distance.y).abs() < y_diff.abs() && ai_tank.power_level < 1.0 {
             self.controls.power_increase();
             changed = true;
         } else {
-            let (y2, p2) = calc_end_y(distance.x,
-                                      ai_tank.power_level - 0.05,
-                                      ai_tank.barrel_orient,
-                                      75.0);
+            let (y2, p2) = flight.calc_end_y(distance.x,
+                                             ai_tank.power_level - 0.05,
+                                             ai_tank.barrel_orient);
             if p2 && (y2 - distance.y).abs() < y_diff.abs() && ai_tank.power_level > 0.0 {
                 self.controls.power_decrease();
                 changed = true;
@@ -117,18 +325,16 @@ impl AiController {
             }
         }
 
-        let (y2, p2) = calc_end_y(distance.x,
-                                  ai_tank.power_level,
-                                  ai_tank.barrel_orient + Deg(0.5),
-                                  75.0);
+        let (y2, p2) = flight.calc_end_y(distance.x,
+                                         ai_tank.power_level,
+                                         ai_tank.barrel_orient + Deg(0.5));
         if !changed && p2 && (y2 - distance.y).abs() < y_diff.abs() {
             self.controls.angle_increase();
             changed = true;
         } else {
-            let (y2, p2) = calc_end_y(distance.x,
-                                      ai_tank.power_level,
-                                      ai_tank.barrel_orient - Deg(0.5),
-                                      75.0);
+            let (y2, p2) = flight.calc_end_y(distance.x,
+                                             ai_tank.power_level,
+                                             ai_tank.barrel_orient - Deg(0.5));
             if !changed && p2 && (y2 - distance.y).abs() < y_diff.abs() {
                 self.controls.angle_decrease();
                 changed = true;
@@ -138,8 +344,41 @@ impl AiController {
         }
 
         if !changed {
-            self.state = TargetingState::Fire;
+            self.miss();
+        }
+    }
+
+    fn miss(&mut self) {
+        let angle = self.skill.angle_error;
+        let power = self.skill.power_error;
+        let angle = self.rng.gen_range(-angle, angle + 1);
+        let power = self.rng.gen_range(-power, power + 1);
+        trace!("Putting the aim off by {} angle and {} power steps", angle, power);
+        self.state = TargetingState::Miss(angle, power);
+    }
+
+    // Moves the aim one step further off each tick, until it is as far off as was decided
+    fn put_off_aim(&mut self, args: specs::RunArg, angle: i32, power: i32) {
+        args.fetch(|_| ());
+        if angle > 0 {
+            self.controls.angle_increase();
+        } else if angle < 0 {
+            self.controls.angle_decrease();
+        } else {
+            self.controls.angle_stop();
+        }
+        if power > 0 {
+            self.controls.power_increase();
+        } else if power < 0 {
+            self.controls.power_decrease();
+        } else {
+            self.controls.power_stop();
         }
+        self.state = if angle == 0 && power == 0 {
+            TargetingState::Fire
+        } else {
+            TargetingState::Miss(angle - angle.signum(), power - power.signum())
+        };
     }
 
     fn fire(&mut self, args: specs::RunArg) {
@@ -152,21 +391,41 @@ impl AiController {
     }
 }
 
-fn calc_end_y(x: f32, power_level: f32, theta: Deg<f32>, mass: f32) -> (f32, bool) {
-    let v = POWER_MIN + POWER_SCALE * power_level;
-    let t = x / (v * theta.sin());
-    let y_end = 0.5 * mass * GRAVITY * t * t + v * t * theta.cos();
-    let v_y_end = mass * GRAVITY * t + v * theta.cos();
-    (y_end, v_y_end < 0.0)
+struct Flight {
+    weapon: Weapon,
+    wind: Wind,
+}
+
+impl Flight {
+    // Steps the shell the same way the physics systems do until it has covered the
+    // horizontal distance x. Returns its height then, and whether it was falling.
+    fn calc_end_y(&self, x: f32, power_level: f32, theta: Deg<f32>) -> (f32, bool) {
+        let mass = self.weapon.mass;
+        let v = POWER_MIN + POWER_SCALE * power_level;
+        let mut velocity = Vector2::new(v * theta.sin(), v * theta.cos());
+        let mut position = Vector2::new(0.0, 0.0);
+        for _ in 0..MAX_FLIGHT_TICKS {
+            if position.x * x.signum() >= x.abs() {
+                return (position.y, velocity.y < 0.0);
+            }
+            velocity.y += mass * GRAVITY * TICK_DELTA;
+            velocity.x += self.wind.acceleration(mass) * TICK_DELTA;
+            apply_drag(&mut velocity, self.weapon.drag, mass, TICK_DELTA);
+            position = position + velocity * TICK_DELTA;
+        }
+        (f32::MIN, false)
+    }
 }
 
 
-impl<C> specs::System<C> for AiController {
-    fn run(&mut self, args: specs::RunArg, _: C) {
+impl specs::System<Tick> for AiController {
+    fn run(&mut self, args: specs::RunArg, tick: Tick) {
         match self.state {
             TargetingState::WaitForTurn => self.wait_for_turn(args),
+            TargetingState::Think(remaining) => self.think(args, remaining, tick.delta),
             TargetingState::SelectTarget => self.select_target(args),
             TargetingState::AlignToTarget(pos) => self.align_to_target(args, pos),
+            TargetingState::Miss(angle, power) => self.put_off_aim(args, angle, power),
             TargetingState::Fire => self.fire(args),
         }
     }
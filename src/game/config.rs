@@ -0,0 +1,312 @@
+use game::{GameSeed, Loadout};
+use game::player::MAX_PLAYERS;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+const DEFAULT_PLAYERS: usize = 4;
+// Keeps names short enough to fit on a tank's label and in the hot-seat banner
+pub const MAX_NAME_LENGTH: usize = 16;
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            "expert" => Some(Difficulty::Expert),
+            _ => None,
+        }
+    }
+}
+
+// How a computer player picks which tank to shoot at, each turn
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Targeting {
+    Nearest,
+    // Lowest health
+    Weakest,
+    // Whoever has dealt the most damage so far
+    Threat,
+    // Whoever last damaged it
+    Revenge,
+    Random,
+}
+
+impl Targeting {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Targeting::Nearest => "nearest",
+            Targeting::Weakest => "weakest",
+            Targeting::Threat => "threat",
+            Targeting::Revenge => "revenge",
+            Targeting::Random => "random",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Targeting> {
+        match name {
+            "nearest" => Some(Targeting::Nearest),
+            "weakest" => Some(Targeting::Weakest),
+            "threat" => Some(Targeting::Threat),
+            "revenge" => Some(Targeting::Revenge),
+            "random" => Some(Targeting::Random),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Controller {
+    Human,
+    Ai(Difficulty, Targeting),
+    // Played by a client connected over the network
+    Network,
+    Vacant,
+}
+
+impl Controller {
+    pub fn ai() -> Controller {
+        Controller::Ai(Difficulty::Normal, Targeting::Nearest)
+    }
+
+    // Accepts "human", "network", "vacant", "ai", "ai:<difficulty>" or
+    // "ai:<difficulty>:<targeting>"
+    pub fn from_name(name: &str) -> Option<Controller> {
+        let mut parts = name.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("human"), None, None) => Some(Controller::Human),
+            (Some("network"), None, None) => Some(Controller::Network),
+            (Some("vacant"), None, None) => Some(Controller::Vacant),
+            (Some("ai"), None, None) => Some(Controller::ai()),
+            (Some("ai"), Some(difficulty), None) => {
+                Difficulty::from_name(difficulty).map(|d| Controller::Ai(d, Targeting::Nearest))
+            }
+            (Some("ai"), Some(difficulty), Some(targeting)) => {
+                match (Difficulty::from_name(difficulty), Targeting::from_name(targeting)) {
+                    (Some(d), Some(t)) => Some(Controller::Ai(d, t)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match *self {
+            Controller::Human => "human".to_string(),
+            Controller::Ai(difficulty, targeting) => {
+                format!("ai:{}:{}", difficulty.name(), targeting.name())
+            }
+            Controller::Network => "network".to_string(),
+            Controller::Vacant => "vacant".to_string(),
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct Slot {
+    pub controller: Controller,
+    pub name: String,
+    pub loadout: Loadout,
+}
+
+impl Slot {
+    fn new(number: usize, controller: Controller) -> Slot {
+        Slot {
+            controller: controller,
+            name: format!("Player {}", number),
+            loadout: Loadout::new(),
+        }
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.trim().chars().take(MAX_NAME_LENGTH).collect();
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct MatchConfig {
+    pub seed: Option<GameSeed>,
+    // Plays a tournament of this many rounds instead of one match after another
+    pub rounds: Option<u32>,
+    pub slots: Vec<Slot>,
+}
+
+impl MatchConfig {
+    pub fn new() -> MatchConfig {
+        let mut config = MatchConfig {
+            seed: None,
+            rounds: None,
+            slots: Vec::new(),
+        };
+        config.set_player_count(DEFAULT_PLAYERS);
+        config.slots[0].controller = Controller::Human;
+        config
+    }
+
+    // The file has a "seed <number>" line, an optional "rounds <number>" line, one
+    // "player <controller> [name]" line per slot and optional "loadout" lines
+    pub fn load(path: &Path) -> io::Result<MatchConfig> {
+        let file = BufReader::new(File::open(path)?);
+        let mut config = MatchConfig {
+            seed: None,
+            rounds: None,
+            slots: Vec::new(),
+        };
+        for line in file.lines() {
+            let line = line?;
+            let line = line.trim();
+            let mut words = line.splitn(2, ' ');
+            match (words.next(), words.next()) {
+                (Some(""), None) => (),
+                (Some(c), _) if c.starts_with('#') => (),
+                (Some("seed"), Some(seed)) => {
+                    match seed.trim().parse() {
+                        Ok(seed) => config.seed = Some(GameSeed(seed)),
+                        Err(_) => return Err(invalid(line)),
+                    }
+                }
+                (Some("rounds"), Some(rounds)) => {
+                    match rounds.trim().parse() {
+                        Ok(rounds) => config.rounds = Some(rounds),
+                        Err(_) => return Err(invalid(line)),
+                    }
+                }
+                (Some("player"), Some(player)) => {
+                    if !config.add_player(player) {
+                        return Err(invalid(line));
+                    }
+                }
+                (Some("loadout"), Some(loadout)) => {
+                    if !config.add_loadout(loadout) {
+                        return Err(invalid(line));
+                    }
+                }
+                _ => return Err(invalid(line)),
+            }
+        }
+        if config.slots.is_empty() {
+            return Err(invalid("no players"));
+        }
+        Ok(config)
+    }
+
+    // Parses "<controller> [name]", as found after "player" in config and replay files
+    pub fn add_player(&mut self, player: &str) -> bool {
+        if self.slots.len() >= MAX_PLAYERS {
+            warn!("Only {} players are supported", MAX_PLAYERS);
+            return false;
+        }
+        let mut words = player.trim().splitn(2, ' ');
+        let controller = match words.next().and_then(Controller::from_name) {
+            Some(c) => c,
+            None => return false,
+        };
+        let mut slot = Slot::new(self.slots.len() + 1, controller);
+        if let Some(name) = words.next() {
+            slot.set_name(name);
+        }
+        self.slots.push(slot);
+        true
+    }
+
+    // Parses "<slot> <goods>=<count>...", as found after "loadout" in config and replay files.
+    // The slot has to have been added already.
+    pub fn add_loadout(&mut self, loadout: &str) -> bool {
+        let mut words = loadout.split_whitespace();
+        let slot = match words.next().and_then(|n| n.parse().ok()) {
+            Some(number) => self.slot_mut(number),
+            None => None,
+        };
+        match slot {
+            Some(slot) => words.all(|item| slot.loadout.parse_item(item)),
+            None => false,
+        }
+    }
+
+    // Also writes a "loadout" line for every player who bought anything
+    pub fn write_players<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for slot in &self.slots {
+            writeln!(out, "player {} {}", slot.controller.name(), slot.name)?;
+        }
+        for (i, slot) in self.slots.iter().enumerate() {
+            if !slot.loadout.is_empty() {
+                writeln!(out, "loadout {} {}", i + 1, slot.loadout.format())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_player_count(&mut self, count: usize) {
+        let count = if count > MAX_PLAYERS {
+            warn!("Only {} players are supported", MAX_PLAYERS);
+            MAX_PLAYERS
+        } else {
+            count
+        };
+        self.slots.truncate(count);
+        while self.slots.len() < count {
+            let number = self.slots.len() + 1;
+            self.slots.push(Slot::new(number, Controller::ai()));
+        }
+    }
+
+    // Without any input, human slots are handed to the computer
+    pub fn without_humans(&mut self) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if slot.controller == Controller::Human {
+                warn!("Player {} is human, but there is no input, using the computer instead",
+                      i + 1);
+                slot.controller = Controller::ai();
+            }
+        }
+    }
+
+    pub fn slots_with(&self, controller: Controller) -> Vec<u8> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|&(_, slot)| slot.controller == controller)
+            .map(|(i, _)| (i as u8) + 1)
+            .collect()
+    }
+
+    // Slots are numbered from 1, like players
+    pub fn slot_mut(&mut self, number: usize) -> Option<&mut Slot> {
+        if number == 0 {
+            None
+        } else {
+            self.slots.get_mut(number - 1)
+        }
+    }
+
+    pub fn slot(&self, number: u8) -> Option<&Slot> {
+        if number == 0 {
+            None
+        } else {
+            self.slots.get(number as usize - 1)
+        }
+    }
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid match config: {}", what))
+}
@@ -15,7 +15,7 @@ impl TankControls {
         }
     }
 
-    fn tc(&mut self, value: TankControl) {
+    pub fn send(&mut self, value: TankControl) {
         if self.tank_control.send(value).is_err() {
             warn!("Controls disconnected");
         }
@@ -26,21 +26,36 @@ impl TankControls {
         }
     }
     pub fn angle_increase(&mut self) {
-        self.tc(TankControl::AngleIncreasing)
+        self.send(TankControl::AngleIncreasing)
     }
     pub fn angle_decrease(&mut self) {
-        self.tc(TankControl::AngleDecreasing)
+        self.send(TankControl::AngleDecreasing)
     }
     pub fn angle_stop(&mut self) {
-        self.tc(TankControl::AngleStop)
+        self.send(TankControl::AngleStop)
     }
     pub fn power_increase(&mut self) {
-        self.tc(TankControl::PowerIncreasing)
+        self.send(TankControl::PowerIncreasing)
     }
     pub fn power_decrease(&mut self) {
-        self.tc(TankControl::PowerDecreasing)
+        self.send(TankControl::PowerDecreasing)
     }
     pub fn power_stop(&mut self) {
-        self.tc(TankControl::PowerStop)
+        self.send(TankControl::PowerStop)
+    }
+    pub fn next_weapon(&mut self) {
+        self.send(TankControl::NextWeapon)
+    }
+    pub fn previous_weapon(&mut self) {
+        self.send(TankControl::PreviousWeapon)
+    }
+    pub fn move_left(&mut self) {
+        self.send(TankControl::MoveLeft)
+    }
+    pub fn move_right(&mut self) {
+        self.send(TankControl::MoveRight)
+    }
+    pub fn move_stop(&mut self) {
+        self.send(TankControl::MoveStop)
     }
 }
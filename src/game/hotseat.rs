@@ -0,0 +1,76 @@
+use cgmath::Point2;
+use game::{ActivePlayer, Player, Players};
+use physics::{Dimensions, Tick};
+use specs;
+use text::{self, Text};
+
+const BANNER_TIME: f32 = 2.5;
+const BANNER_SCALE: f32 = 1.0;
+// Approximate advance of a glyph at scale 1.0, used to centre the banner
+const CHAR_WIDTH: f32 = 16.0;
+
+// Shows a banner whenever the keyboard has to be handed over to another human player
+pub struct HotSeatSystem {
+    humans: Vec<Player>,
+    last_human: Option<Player>,
+    banner: Option<(specs::Entity, f32)>,
+}
+
+impl HotSeatSystem {
+    pub fn new(humans: Vec<Player>) -> HotSeatSystem {
+        HotSeatSystem {
+            humans: humans,
+            last_human: None,
+            banner: None,
+        }
+    }
+}
+
+impl specs::System<Tick> for HotSeatSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
+        let (active, players, dim, mut texts, mut drawables) = arg.fetch(|w| {
+            (w.read_resource::<ActivePlayer>(),
+             w.read_resource::<Players>(),
+             w.read_resource::<Dimensions>(),
+             w.write::<Text>(),
+             w.write::<text::Drawable>())
+        });
+
+        if let Some((banner, remaining)) = self.banner.take() {
+            let remaining = remaining - tick.delta;
+            if remaining > 0.0 {
+                self.banner = Some((banner, remaining));
+            } else {
+                arg.delete(banner);
+            }
+        }
+
+        let player = match active.player() {
+            Some(p) if self.humans.contains(&p) => p,
+            _ => return,
+        };
+        if self.last_human == Some(player) {
+            return;
+        }
+        self.last_human = Some(player);
+
+        let message = format!("Pass the keyboard to {}", players.name(&player));
+        debug!("{}", message);
+
+        if let Some((old, _)) = self.banner.take() {
+            arg.delete(old);
+        }
+        let width = message.chars().count() as f32 * CHAR_WIDTH * BANNER_SCALE;
+        let position = Point2::new((dim.game_width() as f32 - width) / 2.0,
+                                   dim.game_height() as f32 * 0.85);
+        let banner = arg.create_pure();
+        texts.insert(banner,
+                     Text {
+                         text: message,
+                         screen_position: position,
+                         scale: BANNER_SCALE,
+                     });
+        drawables.insert(banner, text::Drawable::new([1.0, 1.0, 1.0]));
+        self.banner = Some((banner, BANNER_TIME));
+    }
+}
@@ -3,73 +3,180 @@ use explosion;
 use gfx;
 use physics::*;
 use projectile;
+use shield;
 use specs;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::mpsc;
+use std::thread;
 use std::time;
 use tank;
 use terrain;
 use text;
 
 mod ai;
+mod config;
 mod controls;
+mod hotseat;
+mod net;
 mod player;
+mod random;
+mod replay;
+mod shop;
 mod state;
+mod stats;
+mod tournament;
+mod wind;
 
 pub use self::ai::AiController;
+pub use self::config::{Controller, Difficulty, MatchConfig, Slot, Targeting};
 pub use self::controls::TankControls;
-pub use self::player::{Player, Players};
+pub use self::net::{NetClient, NetHost};
+pub use self::player::{Player, Players, label_position, label_text};
+pub use self::random::{GameRng, GameSeed};
+pub use self::replay::{Command, CommandLog, Replay, ReplayEvent, log_command};
+pub use self::shop::{Loadout, shop, shop_headless};
 pub use self::state::ActivePlayer;
+pub use self::stats::{Scoreboard, Shot, Statistics};
+pub use self::tournament::Tournament;
 
-#[derive(Debug,PartialEq,Eq)]
+use self::hotseat::HotSeatSystem;
+use self::replay::Playback;
+use self::wind::WindDisplaySystem;
+
+#[derive(Debug,Clone,PartialEq)]
 pub enum QuitStatus {
-    PlayerWon { player: u8, turn: u32 },
-    Draw { turn: u32 },
+    PlayerWon {
+        player: u8,
+        turn: u32,
+        scoreboard: Scoreboard,
+    },
+    Draw { turn: u32, scoreboard: Scoreboard },
     Quit,
 }
 
-pub fn run<W, D, F>(window: &mut W) -> QuitStatus
-    where W: Window<D, F>,
+impl QuitStatus {
+    pub fn scoreboard(&self) -> Option<&Scoreboard> {
+        match *self {
+            QuitStatus::PlayerWon { ref scoreboard, .. } |
+            QuitStatus::Draw { ref scoreboard, .. } => Some(scoreboard),
+            QuitStatus::Quit => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Session {
+    Play {
+        seed: GameSeed,
+        config: MatchConfig,
+        recording: Option<PathBuf>,
+    },
+    Replay(Replay),
+    Host {
+        seed: GameSeed,
+        config: MatchConfig,
+        host: NetHost,
+    },
+    Join(NetClient),
+}
+
+impl Session {
+    fn seed(&self) -> GameSeed {
+        match *self {
+            Session::Play { seed, .. } => seed,
+            Session::Replay(ref replay) => replay.seed,
+            Session::Host { seed, .. } => seed,
+            Session::Join(ref client) => client.seed,
+        }
+    }
+
+    fn config(&self) -> &MatchConfig {
+        match *self {
+            Session::Play { ref config, .. } => config,
+            Session::Replay(ref replay) => &replay.config,
+            Session::Host { ref config, .. } => config,
+            Session::Join(ref client) => &client.config,
+        }
+    }
+}
+
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+pub fn run<W, D, F>(window: &mut W, session: Session) -> QuitStatus
+    where W: GfxWindow<D, F>,
           D: gfx::Device + 'static,
           F: gfx::Factory<D::Resources>,
           D::CommandBuffer: Send
 {
     let (mut device_renderer, enc_queue) = DeviceRenderer::new(window.create_buffers(2));
 
-    let mut w = specs::World::new();
-    setup_world(&mut w, window.get_viewport_size());
-    let mut plan = specs::Planner::new(w);
-    let mut receiver = setup_planner(window, &mut plan, enc_queue);
+    let mut plan = create_planner(window.get_viewport_size(), &session);
+    setup_drawing(window, &mut plan, enc_queue);
+    // Drawn along with the match, but kept out of setup_drawing as the shop has no wind
+    plan.add_system(WindDisplaySystem::new(), "wind-display", 15);
+
+    play(window,
+         plan,
+         session,
+         true,
+         true,
+         |window| { device_renderer.draw(window.get_device()); })
+}
+
+pub fn run_headless<W: Window>(window: &mut W, session: Session) -> QuitStatus {
+    let plan = create_planner(window.get_viewport_size(), &session);
 
-    dispatch_loop(window, &mut device_renderer, plan, &mut receiver)
+    play(window, plan, session, false, false, |_| ())
 }
 
-fn setup_world(world: &mut specs::World, viewport_size: (u32, u32)) {
+fn create_planner(viewport_size: (u32, u32), session: &Session) -> Planner {
+    let mut w = specs::World::new();
+    setup_world(&mut w, viewport_size, session.seed(), &session.config().slots);
+    specs::Planner::new(w)
+}
+
+fn setup_world(world: &mut specs::World,
+               viewport_size: (u32, u32),
+               seed: GameSeed,
+               slots: &[Slot]) {
     world.register::<Position>();
     world.register::<Velocity>();
     world.register::<Mass>();
+    world.register::<Drag>();
     world.register::<tank::Tank>();
+    world.register::<tank::Status>();
+    world.register::<tank::Falling>();
     world.register::<tank::Drawable>();
     world.register::<terrain::Drawable>();
     world.register::<projectile::Drawable>();
     world.register::<projectile::Projectile>();
+    world.register::<projectile::Rolling>();
+    world.register::<projectile::Digging>();
     world.register::<explosion::Explosion>();
     world.register::<explosion::Drawable>();
+    world.register::<shield::Shield>();
+    world.register::<shield::Drawable>();
     world.register::<text::Text>();
     world.register::<text::Drawable>();
 
+    info!("Setting up match with seed {}", seed.0);
+    let mut rng = GameRng::new(seed);
     let dimensions = Dimensions::new(viewport_size.0, viewport_size.1);
-    world.add_resource(terrain::generate(&dimensions, 10));
+    world.add_resource(terrain::generate(&dimensions, 10, &mut rng));
+    world.add_resource(seed);
+    world.add_resource(rng);
     world.add_resource(dimensions);
     world.add_resource(ActivePlayer::new());
+    world.add_resource(Wind::calm());
     world.create().with(terrain::Drawable::new()).build();
-    Players::create(world, 4);
+    Players::create(world, slots);
+    let stats = Statistics::new(&world.read_resource_now::<Players>().to_vec());
+    world.add_resource(stats);
 }
 
-fn setup_planner<W, D, F>(window: &mut W,
-                          planner: &mut Planner,
-                          encoder_queue: EncoderQueue<D>)
-                          -> mpsc::Receiver<QuitStatus>
-    where W: Window<D, F>,
+fn setup_drawing<W, D, F>(window: &mut W, planner: &mut Planner, encoder_queue: EncoderQueue<D>)
+    where W: GfxWindow<D, F>,
           D: gfx::Device + 'static,
           F: gfx::Factory<D::Resources>,
           D::CommandBuffer: Send
@@ -82,83 +189,339 @@ fn setup_planner<W, D, F>(window: &mut W,
         DrawSystem::new(window.get_factory(), rtv, encoder_queue, &terrain)
     };
 
-    let (game_state_system, results_receiver) = state::GameStateSystem::new();
-
     planner.add_system(draw, "drawing", 10);
     planner.add_system(terrain::PreDrawSystem::new(), "draw-prep-terrain", 15);
     planner.add_system(tank::PreDrawSystem::new(), "draw-prep-tank", 15);
     planner.add_system(projectile::PreDrawSystem::new(), "draw-prep-projectile", 15);
     planner.add_system(explosion::PreDrawSystem::new(), "draw-prep-explosion", 15);
+    planner.add_system(shield::PreDrawSystem::new(), "draw-prep-shield", 15);
     planner.add_system(text::PreDrawSystem::new(), "draw-prep-text", 15);
-    planner.add_system(projectile::CollisionSystem::new(), "collide-projectile", 20);
-    planner.add_system(InertiaSystem::new(), "inertia", 30);
+}
+
+fn play<W, R>(window: &mut W,
+              mut planner: Planner,
+              session: Session,
+              human_input: bool,
+              realtime: bool,
+              render: R)
+              -> QuitStatus
+    where W: Window,
+          R: FnMut(&mut W)
+{
+    // Networked peers check that they agree on the turn order
+    let (turn_log, turns) = mpsc::channel();
+    let turn_log = match session {
+        Session::Host { .. } |
+        Session::Join(_) => Some(turn_log),
+        _ => None,
+    };
+    let mut receiver = setup_planner(&mut planner, turn_log);
+    let seed = session.seed();
+
+    match session {
+        Session::Play { seed, mut config, recording } => {
+            if !human_input {
+                config.without_humans();
+            }
+            let (log, commands) = mpsc::channel();
+            let log = recording.as_ref().map(|_| log);
+            let controls = create_controls(&mut planner, log);
+            let remote = assign_controllers(window, &mut planner, controls, &config, seed);
+            for (player, controls) in remote {
+                warn!("Nobody is connected for player {}, using the computer instead",
+                      player.player_number());
+                add_ai(&mut planner,
+                       player,
+                       controls,
+                       Difficulty::Normal,
+                       Targeting::Nearest,
+                       seed);
+            }
+            setup_hot_seat(&mut planner, &config);
+
+            let result = dispatch_loop(window, planner, &mut receiver, realtime, |_| None, render);
+
+            if let Some(path) = recording {
+                let mut replay = Replay::new(seed, config);
+                replay.events.extend(commands.try_iter());
+                replay.result = Some(result.clone());
+                match replay.save(&path) {
+                    Ok(()) => info!("Saved replay to {}", path.display()),
+                    Err(e) => error!("Unable to save replay to {}: {}", path.display(), e),
+                }
+            }
+            result
+        }
+        Session::Replay(replay) => {
+            info!("Playing back {} recorded commands", replay.events.len());
+            let controls = create_controls(&mut planner, None);
+            window.set_controls(HashMap::new());
+            let mut playback = Playback::new(replay.events, controls);
+            setup_hot_seat(&mut planner, &replay.config);
+
+            let result = dispatch_loop(window,
+                                       planner,
+                                       &mut receiver,
+                                       realtime,
+                                       |tick| {
+                                           playback.feed(tick);
+                                           None
+                                       },
+                                       render);
+
+            if replay.result.is_some() && replay.result != Some(result.clone()) {
+                warn!("Replay finished with {:?}, but the recorded result was {:?}",
+                      result,
+                      replay.result);
+            }
+            result
+        }
+        Session::Host { seed, mut config, mut host } => {
+            if !human_input {
+                config.without_humans();
+            }
+            let (log, commands) = mpsc::channel();
+            let controls = create_controls(&mut planner, Some(log));
+            let remote = assign_controllers(window, &mut planner, controls, &config, seed);
+            setup_hot_seat(&mut planner, &config);
+            if let Err(e) = host.start(seed, &config, remote) {
+                error!("Unable to start the network match: {}", e);
+                return QuitStatus::Quit;
+            }
+
+            let result = dispatch_loop(window,
+                                       planner,
+                                       &mut receiver,
+                                       realtime,
+                                       |tick| host.before_tick(tick, &commands, &turns),
+                                       render);
+
+            host.finish(result, &commands, &turns)
+        }
+        Session::Join(mut client) => {
+            let controls = create_controls(&mut planner, None);
+            let mut playback = Playback::new(Vec::new(), controls);
+
+            let player = {
+                planner
+                    .mut_world()
+                    .read_resource_now::<Players>()
+                    .to_vec()
+                    .into_iter()
+                    .find(|p| p.player_number() == client.player)
+            };
+            let player = match player {
+                Some(p) => p,
+                None => {
+                    error!("The host assigned us player {}, which has no tank", client.player);
+                    return QuitStatus::Quit;
+                }
+            };
+            let local = client.local_controls();
+            let mut humans = HashMap::new();
+            if human_input {
+                humans.insert(player.player_number(), local);
+            } else {
+                add_ai(&mut planner,
+                       player,
+                       local,
+                       Difficulty::Normal,
+                       Targeting::Nearest,
+                       seed);
+            }
+            window.set_controls(humans);
+
+            let result = dispatch_loop(window,
+                                       planner,
+                                       &mut receiver,
+                                       realtime,
+                                       |tick| client.before_tick(tick, &mut playback, &turns),
+                                       render);
+
+            client.finish(result, &turns)
+        }
+    }
+}
+
+fn setup_planner(planner: &mut Planner,
+                 turn_log: Option<state::TurnLog>)
+                 -> mpsc::Receiver<QuitStatus> {
+    let (game_state_system, results_receiver) = state::GameStateSystem::new(turn_log);
+
+    // Listed in the order they run, highest priority first
+    planner.add_system(game_state_system, "game-state", 50);
     planner.add_system(GravitySystem::new(), "gravity", 35);
+    planner.add_system(WindSystem::new(), "wind", 35);
     planner.add_system(explosion::ExplosionSystem::new(), "explosion", 35);
-    planner.add_system(game_state_system, "game-state", 50);
-
-    let human_controls = create_controls(planner);
-    window.set_controls(human_controls);
+    planner.add_system(DragSystem::new(), "drag", 34);
+    planner.add_system(projectile::SplitSystem::new(), "split-projectile", 32);
+    planner.add_system(projectile::RollingSystem::new(), "roll-projectile", 31);
+    planner.add_system(projectile::DiggingSystem::new(), "dig-projectile", 31);
+    planner.add_system(InertiaSystem::new(), "inertia", 30);
+    planner.add_system(tank::SettlingSystem::new(), "settle-tank", 25);
+    planner.add_system(projectile::CollisionSystem::new(), "collide-projectile", 20);
+    planner.add_system(shield::ShieldSystem::new(), "shield", 19);
 
     results_receiver
 }
 
-fn create_controls(planner: &mut Planner) -> TankControls {
+fn create_controls(planner: &mut Planner, log: Option<CommandLog>) -> Vec<(Player, TankControls)> {
     let players = {
         planner
             .mut_world()
             .read_resource_now::<Players>()
             .to_vec()
     };
-    let mut human_control = None;
+    let mut controls = Vec::new();
     for player in players {
         let number = player.player_number();
 
-        let (fire_system, fire_control) = projectile::FireControlSystem::new(player);
-        let (tank_system, tank_control) = tank::TankControlSystem::new(player);
-        let controls = TankControls::new(fire_control, tank_control);
+        let (fire_system, fire_control) = projectile::FireControlSystem::new(player, log.clone());
+        let (tank_system, tank_control) = tank::TankControlSystem::new(player, log.clone());
 
         planner.add_system(fire_system, &format!("firing-{}", number), 60);
         planner.add_system(tank_system, &format!("tank-control-{}", number), 61);
 
-        if number == 1 {
-            human_control = Some(controls);
-        } else {
-            info!("Player {} is computer-controlled", number);
-            let ai = AiController::new(player, controls);
-            planner.add_system(ai, &format!("ai-{}", number), 70);
+        controls.push((player, TankControls::new(fire_control, tank_control)));
+    }
+    controls
+}
+
+// Returns the controls of network players, which are up to the session to drive
+fn assign_controllers<W: Window>(window: &mut W,
+                                 planner: &mut Planner,
+                                 controls: Vec<(Player, TankControls)>,
+                                 config: &MatchConfig,
+                                 seed: GameSeed)
+                                 -> Vec<(Player, TankControls)> {
+    let mut humans = HashMap::new();
+    let mut remote = Vec::new();
+    for (player, controls) in controls {
+        let number = player.player_number();
+        let controller = match config.slot(number) {
+            Some(slot) => slot.controller,
+            None => continue,
+        };
+        match controller {
+            Controller::Human => {
+                humans.insert(number, controls);
+            }
+            Controller::Ai(difficulty, targeting) => {
+                add_ai(planner, player, controls, difficulty, targeting, seed)
+            }
+            Controller::Network => remote.push((player, controls)),
+            Controller::Vacant => (),
         }
     }
-    human_control.unwrap()
+    window.set_controls(humans);
+    remote
+}
+
+fn add_ai(planner: &mut Planner,
+          player: Player,
+          controls: TankControls,
+          difficulty: Difficulty,
+          targeting: Targeting,
+          seed: GameSeed) {
+    let number = player.player_number();
+    info!("Player {} is computer-controlled ({}, targeting {})",
+          number,
+          difficulty.name(),
+          targeting.name());
+    let ai = AiController::new(player, controls, difficulty, targeting, seed);
+    planner.add_system(ai, &format!("ai-{}", number), 70);
+}
+
+// With several humans sharing the keyboard, announce whose turn it is
+fn setup_hot_seat(planner: &mut Planner, config: &MatchConfig) {
+    let humans = config.slots_with(Controller::Human);
+    if humans.len() < 2 {
+        return;
+    }
+    let players: Vec<Player> = {
+        planner
+            .mut_world()
+            .read_resource_now::<Players>()
+            .to_vec()
+            .into_iter()
+            .filter(|p| humans.contains(&p.player_number()))
+            .collect()
+    };
+    info!("Hot-seat match between {} human players", players.len());
+    planner.add_system(HotSeatSystem::new(players), "hot-seat", 40);
 }
 
-fn dispatch_loop<W, D, F>(window: &mut W,
-                          device_renderer: &mut DeviceRenderer<D>,
-                          mut planner: specs::Planner<f32>,
-                          game_state: &mut mpsc::Receiver<QuitStatus>)
+// Steps the simulation at a fixed rate. In realtime mode, ticks are accumulated
+// from wall-clock time and only the last tick of each frame is rendered,
+// otherwise one tick is run per frame as fast as possible.
+fn dispatch_loop<W, B, R>(window: &mut W,
+                          mut planner: Planner,
+                          game_state: &mut mpsc::Receiver<QuitStatus>,
+                          realtime: bool,
+                          mut before_tick: B,
+                          mut render: R)
                           -> QuitStatus
-    where W: Window<D, F>,
-          D: gfx::Device + 'static,
-          F: gfx::Factory<D::Resources>,
-          D::CommandBuffer: Send
+    where W: Window,
+          B: FnMut(u64) -> Option<QuitStatus>,
+          R: FnMut(&mut W)
 {
+    let mut tick_number = 0;
+    let mut accumulator = 0.0;
     let mut last_time = time::Instant::now();
     loop {
-        trace!("Dispatching systems");
-        let elapsed = last_time.elapsed();
-        let delta = elapsed.subsec_nanos() as f32 / 1e9 + elapsed.as_secs() as f32;
-        last_time = time::Instant::now();
+        let ticks = if realtime {
+            let elapsed = last_time.elapsed();
+            last_time = time::Instant::now();
+            accumulator += elapsed.subsec_nanos() as f32 / 1e9 + elapsed.as_secs() as f32;
+            let due = (accumulator / TICK_DELTA) as u32;
+            if due > MAX_TICKS_PER_FRAME {
+                debug!("Simulation fell behind by {} ticks, skipping ahead",
+                       due - MAX_TICKS_PER_FRAME);
+                accumulator = 0.0;
+                MAX_TICKS_PER_FRAME
+            } else {
+                accumulator -= due as f32 * TICK_DELTA;
+                due
+            }
+        } else {
+            1
+        };
 
-        planner.dispatch(delta);
+        for i in 0..ticks {
+            tick_number += 1;
+            let tick = Tick {
+                number: tick_number,
+                delta: TICK_DELTA,
+                render: i + 1 == ticks,
+            };
+            if let Some(quit_status) = before_tick(tick.number) {
+                return quit_status;
+            }
+            trace!("Dispatching systems for {:?}", tick);
+            planner.dispatch(tick);
 
-        device_renderer.draw(window.get_device());
-        window.swap_window();
+            if tick.render {
+                render(window);
+                window.swap_window();
+            }
+            planner.wait();
+            let active = planner
+                .mut_world()
+                .read_resource_now::<ActivePlayer>()
+                .player()
+                .map(|p| p.player_number());
+            window.set_active_player(active);
+            if let Ok(quit_status) = game_state.try_recv() {
+                return quit_status;
+            }
+        }
 
         if let Some(quit_status) = window.poll_events() {
             return quit_status;
         }
-        planner.wait();
-        if let Ok(quit_status) = game_state.try_recv() {
-            return quit_status;
+        if ticks == 0 {
+            let remaining = (TICK_DELTA - accumulator).max(0.0);
+            thread::sleep(time::Duration::new(0, (remaining * 1e9) as u32));
         }
     }
 }
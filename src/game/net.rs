@@ -0,0 +1,504 @@
+use game::{Command, Controller, GameSeed, MatchConfig, Player, QuitStatus, ReplayEvent};
+use game::TankControls;
+use game::replay::{Playback, format_result, parse_result};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::str::SplitWhitespace;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tank::TankControl;
+
+// How long to wait for the other peers to report how their match ended
+const RESULT_TIMEOUT: u64 = 10;
+
+// The protocol is line based, using the same words as replay files.
+//
+// Host to client:
+//   welcome <player>            the slot the client plays
+//   seed <seed>                 followed by one "player <controller> <name>" per slot,
+//                               and "loadout <player> <goods>..." for players with purchases
+//   start                       the match is set up
+//   command <tick> <player> <command>
+//   turn <turn> <player>        announced by the host's GameStateSystem
+//   tick <tick>                 the client may simulate up to this tick
+//   result <result>
+//
+// Client to host:
+//   command <command>           input for the client's own player
+//   result <result>
+
+type Messages<T> = mpsc::Receiver<(T, Option<String>)>;
+
+// Lines are read on a background thread, None means the peer disconnected
+fn spawn_reader<T>(stream: TcpStream, tag: T, sender: mpsc::Sender<(T, Option<String>)>)
+    where T: Copy + Send + 'static
+{
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send((tag, Some(line))).is_err() {
+                        return;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        let _ = sender.send((tag, None));
+    });
+}
+
+#[derive(Debug)]
+struct Peer {
+    player: u8,
+    stream: TcpStream,
+}
+
+// The host runs the authoritative simulation. Commands from clients are applied as soon as
+// they arrive, and everything the control systems consumed is forwarded to the clients along
+// with the tick it was consumed on, so they can play the match back exactly.
+#[derive(Debug)]
+pub struct NetHost {
+    peers: Vec<Peer>,
+    messages: Messages<u8>,
+    remote: HashMap<u8, TankControls>,
+    last_tick: u64,
+}
+
+impl NetHost {
+    // The listener's local address is where clients connect, which is only known after
+    // binding when the port is 0
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<TcpListener> {
+        TcpListener::bind(address)
+    }
+
+    // Waits for a client to join for every network slot in the config
+    pub fn accept_players(listener: &TcpListener, config: &MatchConfig) -> io::Result<NetHost> {
+        let slots = config.slots_with(Controller::Network);
+        info!("Waiting for {} players to join on {}",
+              slots.len(),
+              listener.local_addr()?);
+
+        let (sender, messages) = mpsc::channel();
+        let mut peers = Vec::new();
+        for player in slots {
+            let (mut stream, address) = listener.accept()?;
+            info!("Player {} joined from {}", player, address);
+            stream.set_nodelay(true)?;
+            writeln!(stream, "welcome {}", player)?;
+            spawn_reader(stream.try_clone()?, player, sender.clone());
+            peers.push(Peer {
+                           player: player,
+                           stream: stream,
+                       });
+        }
+        Ok(NetHost {
+               peers: peers,
+               messages: messages,
+               remote: HashMap::new(),
+               last_tick: 0,
+           })
+    }
+
+    pub fn start(&mut self,
+                 seed: GameSeed,
+                 config: &MatchConfig,
+                 remote: Vec<(Player, TankControls)>)
+                 -> io::Result<()> {
+        self.remote = remote
+            .into_iter()
+            .map(|(p, c)| (p.player_number(), c))
+            .collect();
+        let mut setup = Vec::new();
+        writeln!(setup, "seed {}", seed.0)?;
+        config.write_players(&mut setup)?;
+        writeln!(setup, "start")?;
+        self.broadcast(&setup)
+    }
+
+    fn broadcast(&mut self, data: &[u8]) -> io::Result<()> {
+        for peer in &mut self.peers {
+            peer.stream.write_all(data)?;
+        }
+        Ok(())
+    }
+
+    // Applies client input before the tick runs, and sends the clients everything
+    // that happened up to the previous tick.
+    pub fn before_tick(&mut self,
+                       tick: u64,
+                       commands: &mpsc::Receiver<ReplayEvent>,
+                       turns: &mpsc::Receiver<(u32, u8)>)
+                       -> Option<QuitStatus> {
+        for (player, message) in self.messages.try_iter() {
+            let line = match message {
+                Some(line) => line,
+                None => {
+                    error!("Player {} disconnected", player);
+                    return Some(QuitStatus::Quit);
+                }
+            };
+            let mut words = line.split_whitespace();
+            let command = match (words.next(), words.next().and_then(Command::from_name)) {
+                (Some("command"), Some(command)) => command,
+                _ => {
+                    warn!("Ignoring unexpected message from player {}: {}", player, line);
+                    continue;
+                }
+            };
+            if let Some(controls) = self.remote.get_mut(&player) {
+                match command {
+                    Command::Fire => controls.fire(),
+                    Command::Tank(control) => controls.send(control),
+                }
+            }
+        }
+
+        let update = updates(tick - 1, commands, turns);
+        if let Err(e) = self.broadcast(&update) {
+            error!("Lost connection to a client: {}", e);
+            return Some(QuitStatus::Quit);
+        }
+        self.last_tick = tick;
+        None
+    }
+
+    // Sends the final tick and result, then checks that every client finished the same way
+    pub fn finish(mut self,
+                  result: QuitStatus,
+                  commands: &mpsc::Receiver<ReplayEvent>,
+                  turns: &mpsc::Receiver<(u32, u8)>)
+                  -> QuitStatus {
+        let mut update = updates(self.last_tick, commands, turns);
+        let _ = writeln!(update, "result {}", format_result(&result));
+        if let Err(e) = self.broadcast(&update) {
+            error!("Unable to send the result to the clients: {}", e);
+            return QuitStatus::Quit;
+        }
+        if result == QuitStatus::Quit {
+            return result;
+        }
+
+        let mut waiting: Vec<u8> = self.peers.iter().map(|p| p.player).collect();
+        let mut agreed = true;
+        while !waiting.is_empty() {
+            let (player, message) =
+                match self.messages.recv_timeout(Duration::from_secs(RESULT_TIMEOUT)) {
+                    Ok(m) => m,
+                    Err(_) => {
+                        error!("Timed out waiting for the results of players {:?}", waiting);
+                        return QuitStatus::Quit;
+                    }
+                };
+            let line = match message {
+                Some(line) => line,
+                None => {
+                    error!("Player {} disconnected before reporting the result", player);
+                    return QuitStatus::Quit;
+                }
+            };
+            let mut words = line.split_whitespace();
+            if words.next() != Some("result") {
+                continue; // Input sent after the match ended
+            }
+            let theirs = parse_result(&mut words);
+            if theirs.as_ref() != Some(&result) {
+                error!("Player {} finished with {:?}, but the host finished with {:?}",
+                       player,
+                       theirs,
+                       result);
+                agreed = false;
+            }
+            waiting.retain(|&p| p != player);
+        }
+        if agreed {
+            info!("All players agree on the result");
+            result
+        } else {
+            QuitStatus::Quit
+        }
+    }
+}
+
+fn updates(tick: u64,
+           commands: &mpsc::Receiver<ReplayEvent>,
+           turns: &mpsc::Receiver<(u32, u8)>)
+           -> Vec<u8> {
+    let mut update = Vec::new();
+    for e in commands.try_iter() {
+        let _ = writeln!(update, "command {} {} {}", e.tick, e.player, e.command.name());
+    }
+    for (turn, player) in turns.try_iter() {
+        let _ = writeln!(update, "turn {} {}", turn, player);
+    }
+    let _ = writeln!(update, "tick {}", tick);
+    update
+}
+
+// A client plays back what the host sends it, never running ahead of the host.
+// Input for its own player goes to the host, which decides when it is applied.
+#[derive(Debug)]
+pub struct NetClient {
+    pub player: u8,
+    pub seed: GameSeed,
+    pub config: MatchConfig,
+    stream: TcpStream,
+    messages: Messages<()>,
+    permitted: u64,
+    host_turns: VecDeque<(u32, u8)>,
+    input: Option<(mpsc::Receiver<()>, mpsc::Receiver<TankControl>)>,
+}
+
+impl NetClient {
+    // Blocks until the host has set up the match
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<NetClient> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let (sender, messages) = mpsc::channel();
+        spawn_reader(stream.try_clone()?, (), sender);
+
+        let mut player = None;
+        let mut seed = None;
+        let mut config = MatchConfig {
+            seed: None,
+            rounds: None,
+            slots: Vec::new(),
+        };
+        loop {
+            let line = match messages.recv() {
+                Ok((_, Some(line))) => line,
+                _ => return Err(invalid("host disconnected during setup")),
+            };
+            let mut words = line.splitn(2, ' ');
+            match (words.next(), words.next()) {
+                (Some("welcome"), Some(p)) => player = p.trim().parse().ok(),
+                (Some("seed"), Some(s)) => seed = s.trim().parse().ok().map(GameSeed),
+                (Some("player"), Some(p)) => {
+                    if !config.add_player(p) {
+                        return Err(invalid(&line));
+                    }
+                }
+                (Some("loadout"), Some(l)) => {
+                    if !config.add_loadout(l) {
+                        return Err(invalid(&line));
+                    }
+                }
+                (Some("start"), None) => break,
+                _ => return Err(invalid(&line)),
+            }
+        }
+
+        match (player, seed) {
+            (Some(player), Some(seed)) if config.slot(player).is_some() => {
+                info!("Joined as player {}", player);
+                Ok(NetClient {
+                       player: player,
+                       seed: seed,
+                       config: config,
+                       stream: stream,
+                       messages: messages,
+                       permitted: 0,
+                       host_turns: VecDeque::new(),
+                       input: None,
+                   })
+            }
+            _ => Err(invalid("missing player or seed")),
+        }
+    }
+
+    // Controls for our own player, which forward everything to the host
+    pub fn local_controls(&mut self) -> TankControls {
+        let (fire_sender, fire) = mpsc::channel();
+        let (tank_sender, tank) = mpsc::channel();
+        self.input = Some((fire, tank));
+        TankControls::new(fire_sender, tank_sender)
+    }
+
+    pub fn before_tick(&mut self,
+                       tick: u64,
+                       playback: &mut Playback,
+                       turns: &mpsc::Receiver<(u32, u8)>)
+                       -> Option<QuitStatus> {
+        let mut input = Vec::new();
+        if let Some((ref fire, ref tank)) = self.input {
+            for control in tank.try_iter() {
+                let _ = writeln!(input, "command {}", Command::Tank(control).name());
+            }
+            for _ in fire.try_iter() {
+                let _ = writeln!(input, "command {}", Command::Fire.name());
+            }
+        }
+        if !input.is_empty() {
+            if let Err(e) = self.stream.write_all(&input) {
+                error!("Lost connection to the host: {}", e);
+                return Some(QuitStatus::Quit);
+            }
+        }
+
+        for turn in turns.try_iter() {
+            let expected = self.host_turns.pop_front();
+            if expected != Some(turn) {
+                error!("Out of sync with the host: turn {:?} started here, but {:?} on the host",
+                       turn,
+                       expected);
+                return Some(QuitStatus::Quit);
+            }
+        }
+
+        while self.permitted < tick {
+            let line = match self.messages.recv() {
+                Ok((_, Some(line))) => line,
+                _ => {
+                    error!("Lost connection to the host");
+                    return Some(QuitStatus::Quit);
+                }
+            };
+            if let Some(status) = self.handle(&line, playback) {
+                return Some(status);
+            }
+        }
+        playback.feed(tick);
+        None
+    }
+
+    fn handle(&mut self, line: &str, playback: &mut Playback) -> Option<QuitStatus> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("command") => {
+                let tick = words.next().and_then(|w| w.parse().ok());
+                let player = words.next().and_then(|w| w.parse().ok());
+                let command = words.next().and_then(Command::from_name);
+                match (tick, player, command) {
+                    (Some(tick), Some(player), Some(command)) => {
+                        playback.push(ReplayEvent {
+                                          tick: tick,
+                                          player: player,
+                                          command: command,
+                                      })
+                    }
+                    _ => warn!("Ignoring invalid command from the host: {}", line),
+                }
+            }
+            Some("turn") => self.queue_turn(line, &mut words),
+            Some("tick") => {
+                match words.next().and_then(|w| w.parse().ok()) {
+                    Some(tick) => self.permitted = tick,
+                    None => warn!("Ignoring invalid tick from the host: {}", line),
+                }
+            }
+            Some("result") => {
+                // The host only finishes early if it quit, or if we are out of sync
+                let result = parse_result(&mut words);
+                if result != Some(QuitStatus::Quit) {
+                    error!("Out of sync with the host, which finished with {:?}", result);
+                }
+                return Some(QuitStatus::Quit);
+            }
+            _ => warn!("Ignoring unexpected message from the host: {}", line),
+        }
+        None
+    }
+
+    fn queue_turn(&mut self, line: &str, words: &mut SplitWhitespace) {
+        let turn = words.next().and_then(|w| w.parse().ok());
+        let player = words.next().and_then(|w| w.parse().ok());
+        match (turn, player) {
+            (Some(turn), Some(player)) => self.host_turns.push_back((turn, player)),
+            _ => warn!("Ignoring invalid turn from the host: {}", line),
+        }
+    }
+
+    // Checks that the host finished the same way, and reports our result back
+    pub fn finish(mut self,
+                  result: QuitStatus,
+                  turns: &mpsc::Receiver<(u32, u8)>)
+                  -> QuitStatus {
+        if result == QuitStatus::Quit {
+            return result;
+        }
+        let host_result = self.wait_for_result();
+        if let Err(e) = writeln!(self.stream, "result {}", format_result(&result)) {
+            warn!("Unable to report the result to the host: {}", e);
+        }
+        // Turns started on the final tick are only announced along with the result
+        let turns: Vec<(u32, u8)> = turns.try_iter().collect();
+        let host_turns: Vec<(u32, u8)> = self.host_turns.drain(..).collect();
+        if turns != host_turns {
+            error!("Out of sync with the host: turns {:?} started here, but {:?} on the host",
+                   turns,
+                   host_turns);
+            return QuitStatus::Quit;
+        }
+        if host_result.as_ref() == Some(&result) {
+            info!("The host agrees on the result");
+            result
+        } else {
+            error!("Finished with {:?}, but the host finished with {:?}",
+                   result,
+                   host_result);
+            QuitStatus::Quit
+        }
+    }
+
+    // Also queues any turns announced on the final tick, which arrive just before the result
+    fn wait_for_result(&mut self) -> Option<QuitStatus> {
+        loop {
+            let message = self.messages.recv_timeout(Duration::from_secs(RESULT_TIMEOUT));
+            let line = match message {
+                Ok((_, Some(line))) => line,
+                _ => return None,
+            };
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("result") => return parse_result(&mut words),
+                Some("turn") => self.queue_turn(&line, &mut words),
+                _ => (),
+            }
+        }
+    }
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid network message: {}", what))
+}
+
+#[cfg(test)]
+mod tests {
+    use draw::HeadlessWindow;
+    use game::{Controller, Difficulty, GameSeed, MatchConfig, QuitStatus, Session, Targeting,
+               run_headless};
+    use std::thread;
+    use super::{NetClient, NetHost};
+
+    // Gives up on the match well before the test harness would
+    const FRAME_LIMIT: u32 = 100_000;
+
+    #[test]
+    fn host_and_client_agree_over_loopback() {
+        let mut config = MatchConfig::new();
+        config.set_player_count(2);
+        config.slots[0].controller = Controller::Ai(Difficulty::Expert, Targeting::Nearest);
+        config.slots[1].controller = Controller::Network;
+
+        let listener = NetHost::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let client = NetClient::connect(address).unwrap();
+            run_headless(&mut HeadlessWindow::new(Some(FRAME_LIMIT)), Session::Join(client))
+        });
+
+        let host = NetHost::accept_players(&listener, &config).unwrap();
+        let session = Session::Host {
+            seed: GameSeed(1),
+            config: config,
+            host: host,
+        };
+        let result = run_headless(&mut HeadlessWindow::new(Some(FRAME_LIMIT)), session);
+        let client_result = client.join().unwrap();
+
+        assert!(result != QuitStatus::Quit);
+        assert_eq!(result, client_result);
+    }
+}
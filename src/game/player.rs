@@ -1,6 +1,8 @@
 use cgmath::Point2;
+use game::{Controller, GameRng, Slot};
 use physics::*;
-use rand::{self, Rng};
+use rand::Rng;
+use shield;
 use specs;
 use tank;
 use terrain;
@@ -9,52 +11,86 @@ use text;
 #[derive(Debug)]
 pub struct Players {
     players: Vec<Player>,
+    names: Vec<String>,
 }
 
-const COLORS: [[f32; 3]; 4] = [
+pub const MAX_PLAYERS: usize = 8;
+
+const COLORS: [[f32; 3]; MAX_PLAYERS] = [
     [1.0, 0.0, 0.0], // red
     [0.0, 0.0, 1.0], // blue
     [1.0, 1.0, 0.0], // yellow
     [0.8, 0.0, 0.8], // purple
+    [0.0, 1.0, 1.0], // cyan
+    [1.0, 0.5, 0.0], // orange
+    [1.0, 0.6, 0.7], // pink
+    [1.0, 1.0, 1.0], // white
 ];
 
 impl Players {
-    pub fn create(world: &mut specs::World, count: usize) {
+    // Vacant slots get no tank, but the remaining players keep their slot number and color
+    pub fn create(world: &mut specs::World, slots: &[Slot]) {
+        let occupied: Vec<usize> = slots
+            .iter()
+            .enumerate()
+            .filter(|&(_, slot)| slot.controller != Controller::Vacant)
+            .map(|(i, _)| i)
+            .collect();
         let dx = {
             let dim = world.read_resource_now::<Dimensions>();
-            dim.game_width() as f32 / ((count + 1) as f32)
+            dim.game_width() as f32 / ((occupied.len() + 1) as f32)
         };
-        let mut rng = rand::thread_rng();
         let mut players = Vec::new();
-        for (i, color) in COLORS.iter().enumerate().take(count) {
-            let x = (i as f32 * dx) + rng.gen_range(dx / 2.0, 3.0 * dx / 2.0);
+        let mut names = Vec::new();
+        for (position, &i) in occupied.iter().enumerate() {
+            let color = &COLORS[i];
+            let offset = world
+                .write_resource_now::<GameRng>()
+                .gen_range(dx / 2.0, 3.0 * dx / 2.0);
+            let x = (position as f32 * dx) + offset;
             let drawable = tank::Drawable::new(*color);
 
             let terrain = world.read_resource_now::<terrain::Terrain>();
             let terrain_height = terrain.get_height(x);
             let normal_dir = terrain.get_normal_dir(x);
 
-            let entity = world
+            let mut tank = tank::Tank::new();
+            slots[i].loadout.equip(&mut tank);
+            let status = tank::Status::new();
+            let label = label_text(&slots[i].name, &tank, Some(&status));
+            let mut builder = world
                 .create()
-                .with(tank::Tank::new())
+                .with(tank)
+                .with(status)
                 .with(drawable)
                 .with(Position::new(x, terrain_height, normal_dir, 20.0))
                 .with(text::Drawable::new([1.0, 1.0, 1.0]))
                 .with(text::Text {
-                          text: format!("Player {}", i + 1),
-                          screen_position: Point2 {
-                              x: x - 25.0,
-                              y: terrain_height + 50.0,
-                          },
+                          text: label,
+                          screen_position: label_position(Point2::new(x, terrain_height)),
                           scale: 0.5,
-                      })
-                .build();
+                      });
+            if let Some(shield) = slots[i].loadout.shield() {
+                builder = builder.with(shield).with(shield::Drawable::new());
+            }
+            let entity = builder.build();
             players.push(Player {
                              player_number: (i as u8) + 1,
                              tank_id: entity,
                          });
+            names.push(slots[i].name.clone());
         }
-        world.add_resource(Players { players: players });
+        world.add_resource(Players {
+                               players: players,
+                               names: names,
+                           });
+    }
+
+    pub fn name(&self, player: &Player) -> &str {
+        self.players
+            .iter()
+            .position(|p| p == player)
+            .map_or("", |i| &self.names[i])
     }
 
     pub fn get_remaining(&self, world: &specs::World) -> Vec<Player> {
@@ -65,11 +101,31 @@ impl Players {
             .collect()
     }
 
+    pub fn by_tank(&self, tank: specs::Entity) -> Option<Player> {
+        self.players.iter().find(|p| p.tank_id == tank).cloned()
+    }
+
     pub fn to_vec(&self) -> Vec<Player> {
         self.players[..].to_vec()
     }
 }
 
+pub fn label_position(tank_position: Point2<f32>) -> Point2<f32> {
+    Point2::new(tank_position.x - 25.0, tank_position.y + 50.0)
+}
+
+pub fn label_text(name: &str, tank: &tank::Tank, status: Option<&tank::Status>) -> String {
+    let label = match tank.arsenal.count(tank.weapon) {
+        Some(count) => format!("{} [{} x{}]", name, tank.weapon.name(), count),
+        None => format!("{} [{}]", name, tank.weapon.name()),
+    };
+    let label = match status.map(|s| s.format()) {
+        Some(ref effects) if !effects.is_empty() => format!("{} ({})", label, effects),
+        _ => label,
+    };
+    label.chars().take(text::MAX_LENGTH).collect()
+}
+
 #[derive(Debug,Copy,Clone,PartialEq,Eq)]
 pub struct Player {
     player_number: u8,
@@ -0,0 +1,31 @@
+use rand::{self, Rng, SeedableRng, XorShiftRng};
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct GameSeed(pub u64);
+
+impl GameSeed {
+    pub fn random() -> GameSeed {
+        GameSeed(rand::random())
+    }
+}
+
+// The single source of randomness for a match, so that a seed always
+// regenerates the same battlefield.
+pub struct GameRng {
+    rng: XorShiftRng,
+}
+
+impl GameRng {
+    pub fn new(seed: GameSeed) -> GameRng {
+        let low = seed.0 as u32;
+        let high = (seed.0 >> 32) as u32;
+        // XorShift must not be seeded with all zeros
+        GameRng { rng: XorShiftRng::from_seed([low, high, low ^ 0x9e37_79b9, high ^ 0x7f4a_7c15]) }
+    }
+}
+
+impl Rng for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+}
@@ -0,0 +1,239 @@
+use game::{GameSeed, MatchConfig, Player, QuitStatus, Scoreboard, TankControls};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::{FromStr, SplitWhitespace};
+use std::sync::mpsc;
+use tank::TankControl;
+
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum Command {
+    Fire,
+    Tank(TankControl),
+}
+
+impl Command {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Command::Fire => "fire",
+            Command::Tank(control) => control.name(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Command> {
+        match name {
+            "fire" => Some(Command::Fire),
+            _ => TankControl::from_name(name).map(Command::Tank),
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct ReplayEvent {
+    pub tick: u64,
+    pub player: u8,
+    pub command: Command,
+}
+
+pub type CommandLog = mpsc::Sender<ReplayEvent>;
+
+// Commands are logged by the control systems at the tick they are applied, so that feeding
+// them back before the same tick reproduces the match exactly.
+pub fn log_command(log: &Option<CommandLog>, tick: u64, player: &Player, command: Command) {
+    if let Some(ref log) = *log {
+        let event = ReplayEvent {
+            tick: tick,
+            player: player.player_number(),
+            command: command,
+        };
+        if log.send(event).is_err() {
+            warn!("Replay recording disconnected");
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    pub seed: GameSeed,
+    pub config: MatchConfig,
+    pub result: Option<QuitStatus>,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    pub fn new(seed: GameSeed, config: MatchConfig) -> Replay {
+        Replay {
+            seed: seed,
+            config: config,
+            result: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let file = BufReader::new(File::open(path)?);
+        let mut seed = None;
+        let mut config = MatchConfig {
+            seed: None,
+            rounds: None,
+            slots: Vec::new(),
+        };
+        let mut result = None;
+        let mut events = Vec::new();
+        for line in file.lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                None => (),
+                Some("seed") => seed = Some(GameSeed(next_value(&mut words, &line)?)),
+                Some("player") => {
+                    let player: Vec<&str> = words.collect();
+                    if !config.add_player(&player.join(" ")) {
+                        return Err(invalid(&line));
+                    }
+                }
+                Some("loadout") => {
+                    let loadout: Vec<&str> = words.collect();
+                    if !config.add_loadout(&loadout.join(" ")) {
+                        return Err(invalid(&line));
+                    }
+                }
+                Some("command") => {
+                    let tick = next_value(&mut words, &line)?;
+                    let player = next_value(&mut words, &line)?;
+                    let command = words
+                        .next()
+                        .and_then(Command::from_name)
+                        .ok_or_else(|| invalid(&line))?;
+                    events.push(ReplayEvent {
+                                    tick: tick,
+                                    player: player,
+                                    command: command,
+                                });
+                }
+                Some("result") => {
+                    result = Some(parse_result(&mut words).ok_or_else(|| invalid(&line))?)
+                }
+                _ => return Err(invalid(&line)),
+            }
+        }
+        match seed {
+            Some(seed) if !config.slots.is_empty() => {
+                Ok(Replay {
+                       seed: seed,
+                       config: config,
+                       result: result,
+                       events: events,
+                   })
+            }
+            _ => Err(invalid("missing seed or players")),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "seed {}", self.seed.0)?;
+        self.config.write_players(&mut file)?;
+        for e in &self.events {
+            writeln!(file, "command {} {} {}", e.tick, e.player, e.command.name())?;
+        }
+        if let Some(ref result) = self.result {
+            writeln!(file, "result {}", format_result(result))?;
+        }
+        Ok(())
+    }
+}
+
+// Results are written as "won <player> <turn> <stats>...", "draw <turn> <stats>..." or
+// "quit", with the stats of every player at the end
+pub fn format_result(result: &QuitStatus) -> String {
+    match *result {
+        QuitStatus::PlayerWon { player, turn, ref scoreboard } => {
+            format!("won {} {} {}", player, turn, scoreboard.format())
+        }
+        QuitStatus::Draw { turn, ref scoreboard } => {
+            format!("draw {} {}", turn, scoreboard.format())
+        }
+        QuitStatus::Quit => "quit".to_string(),
+    }
+}
+
+pub fn parse_result(words: &mut SplitWhitespace) -> Option<QuitStatus> {
+    match words.next() {
+        Some("won") => {
+            match (next_word(words), next_word(words), Scoreboard::parse(words)) {
+                (Some(player), Some(turn), Some(scoreboard)) => {
+                    Some(QuitStatus::PlayerWon {
+                             player: player,
+                             turn: turn,
+                             scoreboard: scoreboard,
+                         })
+                }
+                _ => None,
+            }
+        }
+        Some("draw") => {
+            match (next_word(words), Scoreboard::parse(words)) {
+                (Some(turn), Some(scoreboard)) => {
+                    Some(QuitStatus::Draw {
+                             turn: turn,
+                             scoreboard: scoreboard,
+                         })
+                }
+                _ => None,
+            }
+        }
+        Some("quit") => Some(QuitStatus::Quit),
+        _ => None,
+    }
+}
+
+fn next_word<T: FromStr>(words: &mut SplitWhitespace) -> Option<T> {
+    words.next().and_then(|w| w.parse().ok())
+}
+
+fn next_value<T: FromStr>(words: &mut SplitWhitespace, line: &str) -> io::Result<T> {
+    next_word(words).ok_or_else(|| invalid(line))
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Invalid replay: {}", what))
+}
+
+pub struct Playback {
+    events: VecDeque<ReplayEvent>,
+    controls: HashMap<u8, TankControls>,
+}
+
+impl Playback {
+    pub fn new(events: Vec<ReplayEvent>, controls: Vec<(Player, TankControls)>) -> Playback {
+        Playback {
+            events: events.into_iter().collect(),
+            controls: controls
+                .into_iter()
+                .map(|(p, c)| (p.player_number(), c))
+                .collect(),
+        }
+    }
+
+    // Events have to arrive in tick order
+    pub fn push(&mut self, event: ReplayEvent) {
+        self.events.push_back(event);
+    }
+
+    pub fn feed(&mut self, tick: u64) {
+        while self.events.front().map_or(false, |e| e.tick <= tick) {
+            let event = self.events.pop_front().unwrap();
+            match self.controls.get_mut(&event.player) {
+                Some(controls) => {
+                    match event.command {
+                        Command::Fire => controls.fire(),
+                        Command::Tank(control) => controls.send(control),
+                    }
+                }
+                None => warn!("Replay has a command for unknown player {}", event.player),
+            }
+        }
+    }
+}
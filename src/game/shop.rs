@@ -0,0 +1,435 @@
+use cgmath::Point2;
+use draw::*;
+use game::{Controller, Difficulty, GameSeed, MatchConfig, QuitStatus, TankControls};
+use game::ai;
+use gfx;
+use physics::{Dimensions, TICK_DELTA, Tick};
+use projectile::{Arsenal, WeaponKind};
+use shield::Shield;
+use specs;
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+use tank::{Tank, TankControl};
+use text::{self, Text};
+
+const MATCH_PAY: u32 = 100;
+const MONEY_PER_DAMAGE: f32 = 2.0;
+const KILL_REWARD: u32 = 150;
+const WIN_REWARD: u32 = 250;
+// Damage soaked up by each shield bought
+const SHIELD_STRENGTH: f32 = 50.0;
+const SHIELD_RADIUS: f32 = 35.0;
+
+const LINE_SCALE: f32 = 0.6;
+const LINE_SPACING: f32 = 40.0;
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Goods {
+    Shells(WeaponKind),
+    Shield,
+    Parachute,
+}
+
+impl Goods {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Goods::Shells(kind) => kind.name(),
+            Goods::Shield => "shield",
+            Goods::Parachute => "parachute",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Goods> {
+        match name {
+            "shield" => Some(Goods::Shield),
+            "parachute" => Some(Goods::Parachute),
+            _ => WeaponKind::from_name(name).map(Goods::Shells),
+        }
+    }
+}
+
+#[derive(Debug,Copy,Clone)]
+pub struct Offer {
+    pub goods: Goods,
+    pub quantity: u32,
+    pub price: u32,
+}
+
+pub const CATALOGUE: [Offer; 7] = [Offer {
+                                       goods: Goods::Shells(WeaponKind::BabyMissile),
+                                       quantity: 5,
+                                       price: 100,
+                                   },
+                                   Offer {
+                                       goods: Goods::Shells(WeaponKind::Nuke),
+                                       quantity: 1,
+                                       price: 600,
+                                   },
+                                   Offer {
+                                       goods: Goods::Shells(WeaponKind::Mirv),
+                                       quantity: 2,
+                                       price: 500,
+                                   },
+                                   Offer {
+                                       goods: Goods::Shells(WeaponKind::Roller),
+                                       quantity: 3,
+                                       price: 400,
+                                   },
+                                   Offer {
+                                       goods: Goods::Shells(WeaponKind::Digger),
+                                       quantity: 3,
+                                       price: 300,
+                                   },
+                                   Offer {
+                                       goods: Goods::Shield,
+                                       quantity: 1,
+                                       price: 350,
+                                   },
+                                   Offer {
+                                       goods: Goods::Parachute,
+                                       quantity: 1,
+                                       price: 150,
+                                   }];
+
+pub fn offer(goods: Goods) -> &'static Offer {
+    CATALOGUE.iter().find(|o| o.goods == goods).unwrap()
+}
+
+// What a player takes into their next match, and the money left to buy more with
+#[derive(Debug,Clone,PartialEq)]
+pub struct Loadout {
+    pub money: u32,
+    pub arsenal: Arsenal,
+    // Only counted here, the tank is given the shields once they can be put around it
+    pub shields: u32,
+    pub parachutes: u32,
+}
+
+impl Loadout {
+    pub fn new() -> Loadout {
+        Loadout {
+            money: 0,
+            arsenal: Arsenal::new(),
+            shields: 0,
+            parachutes: 0,
+        }
+    }
+
+    pub fn equip(&self, tank: &mut Tank) {
+        tank.arsenal = self.arsenal.clone();
+        tank.parachutes = self.parachutes;
+    }
+
+    // Every shield bought goes into a single stronger one
+    pub fn shield(&self) -> Option<Shield> {
+        if self.shields > 0 {
+            Some(Shield::new(self.shields as f32 * SHIELD_STRENGTH, SHIELD_RADIUS))
+        } else {
+            None
+        }
+    }
+
+    pub fn owned(&self, goods: Goods) -> u32 {
+        match goods {
+            Goods::Shells(kind) => self.arsenal.count(kind).unwrap_or(0),
+            Goods::Shield => self.shields,
+            Goods::Parachute => self.parachutes,
+        }
+    }
+
+    fn add(&mut self, goods: Goods, quantity: u32) {
+        match goods {
+            Goods::Shells(kind) => self.arsenal.add(kind, quantity),
+            Goods::Shield => self.shields += quantity,
+            Goods::Parachute => self.parachutes += quantity,
+        }
+    }
+
+    pub fn buy(&mut self, offer: &Offer) -> bool {
+        if offer.price > self.money {
+            return false;
+        }
+        self.money -= offer.price;
+        self.add(offer.goods, offer.quantity);
+        true
+    }
+
+    // Purchases only last for the match after they are bought
+    fn use_up(&mut self) {
+        self.arsenal = Arsenal::new();
+        self.shields = 0;
+        self.parachutes = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.format().is_empty()
+    }
+
+    // Written as "<goods>=<count>" for everything owned. The money only matters between
+    // matches, so it is left out.
+    pub fn format(&self) -> String {
+        let mut items = self.arsenal.format();
+        if self.shields > 0 {
+            items.push(format!("shield={}", self.shields));
+        }
+        if self.parachutes > 0 {
+            items.push(format!("parachute={}", self.parachutes));
+        }
+        items.join(" ")
+    }
+
+    pub fn parse_item(&mut self, item: &str) -> bool {
+        let mut parts = item.splitn(2, '=');
+        match (parts.next().and_then(Goods::from_name),
+               parts.next().and_then(|n| n.parse().ok())) {
+            (Some(Goods::Shells(WeaponKind::Missile)), _) => false,
+            (Some(goods), Some(count)) => {
+                self.add(goods, count);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Pays every player for how they did in the match, and clears out what they bought for it
+pub fn pay(config: &mut MatchConfig, result: &QuitStatus) {
+    let scoreboard = match result.scoreboard() {
+        Some(scoreboard) => scoreboard,
+        None => return,
+    };
+    let winner = match *result {
+        QuitStatus::PlayerWon { player, .. } => Some(player),
+        _ => None,
+    };
+    for stats in &scoreboard.players {
+        if let Some(slot) = config.slot_mut(stats.player as usize) {
+            let mut earned = MATCH_PAY + (stats.damage_dealt * MONEY_PER_DAMAGE) as u32 +
+                             stats.kills * KILL_REWARD;
+            if winner == Some(stats.player) {
+                earned += WIN_REWARD;
+            }
+            slot.loadout.use_up();
+            slot.loadout.money += earned;
+            info!("{} earned ${} and now has ${}",
+                  slot.name,
+                  earned,
+                  slot.loadout.money);
+        }
+    }
+}
+
+// Network players aren't at this machine to do any shopping, so they keep what they have
+fn shops_here(controller: Controller) -> bool {
+    match controller {
+        Controller::Human | Controller::Ai(..) => true,
+        Controller::Network | Controller::Vacant => false,
+    }
+}
+
+// Without a display, everyone shops like the computer
+pub fn shop_headless(config: &mut MatchConfig, result: &QuitStatus) {
+    pay(config, result);
+    for slot in config.slots.iter_mut().filter(|s| shops_here(s.controller)) {
+        let difficulty = match slot.controller {
+            Controller::Ai(difficulty, _) => difficulty,
+            _ => Difficulty::Normal,
+        };
+        ai::choose_purchases(&mut slot.loadout, difficulty);
+    }
+}
+
+// Pays out for the match, lets the computer players shop, then shows the shop to each human
+// in turn. Returns false if the game was quit.
+pub fn shop<W, D, F>(window: &mut W, config: &mut MatchConfig, result: &QuitStatus) -> bool
+    where W: GfxWindow<D, F>,
+          D: gfx::Device + 'static,
+          F: gfx::Factory<D::Resources>,
+          D::CommandBuffer: Send
+{
+    pay(config, result);
+    let mut customers = Vec::new();
+    for (i, slot) in config.slots.iter_mut().enumerate() {
+        if !shops_here(slot.controller) {
+            continue;
+        }
+        match slot.controller {
+            Controller::Ai(difficulty, _) => ai::choose_purchases(&mut slot.loadout, difficulty),
+            _ => customers.push(((i as u8) + 1, slot.name.clone())),
+        }
+    }
+    if customers.is_empty() {
+        return true;
+    }
+
+    let (mut device_renderer, enc_queue) = DeviceRenderer::new(window.create_buffers(2));
+    let mut world = specs::World::new();
+    // A fresh battlefield makes a backdrop, without any tanks on it
+    super::setup_world(&mut world, window.get_viewport_size(), GameSeed::random(), &[]);
+    let mut planner = specs::Planner::new(world);
+    super::setup_drawing(window, &mut planner, enc_queue);
+
+    let (fire_control, fire) = mpsc::channel();
+    let (tank_control, tank) = mpsc::channel();
+    let (done, purchases) = mpsc::channel();
+    let loadouts = customers
+        .iter()
+        .map(|&(number, ref name)| {
+                 let loadout = config.slot(number).unwrap().loadout.clone();
+                 (number, name.clone(), loadout)
+             })
+        .collect();
+    planner.add_system(ShopSystem::new(loadouts, fire, tank, done), "shop", 50);
+
+    let controls = TankControls::new(fire_control, tank_control);
+    window.set_controls(customers
+                            .iter()
+                            .map(|&(number, _)| (number, controls.clone()))
+                            .collect::<HashMap<u8, TankControls>>());
+
+    let mut remaining = customers.len();
+    let mut tick_number = 0;
+    window.set_active_player(customers.first().map(|&(number, _)| number));
+    while remaining > 0 {
+        tick_number += 1;
+        planner.dispatch(Tick {
+                             number: tick_number,
+                             delta: TICK_DELTA,
+                             render: true,
+                         });
+        device_renderer.draw(window.get_device());
+        window.swap_window();
+        planner.wait();
+
+        for (number, loadout) in purchases.try_iter() {
+            if let Some(slot) = config.slot_mut(number as usize) {
+                slot.loadout = loadout;
+            }
+            remaining -= 1;
+            let next = customers.len() - remaining;
+            window.set_active_player(customers.get(next).map(|&(number, _)| number));
+        }
+        if window.poll_events().is_some() {
+            return false;
+        }
+        thread::sleep(time::Duration::new(0, (TICK_DELTA * 1e9) as u32));
+    }
+    true
+}
+
+// Up and down pick from the catalogue, and fire buys. The last line leaves the shop.
+struct ShopSystem {
+    customers: Vec<(u8, String, Loadout)>,
+    selected: usize,
+    fire: mpsc::Receiver<()>,
+    tank: mpsc::Receiver<TankControl>,
+    done: mpsc::Sender<(u8, Loadout)>,
+    lines: Vec<specs::Entity>,
+}
+
+impl ShopSystem {
+    fn new(mut customers: Vec<(u8, String, Loadout)>,
+           fire: mpsc::Receiver<()>,
+           tank: mpsc::Receiver<TankControl>,
+           done: mpsc::Sender<(u8, Loadout)>)
+           -> ShopSystem {
+        // Served from the back
+        customers.reverse();
+        ShopSystem {
+            customers: customers,
+            selected: 0,
+            fire: fire,
+            tank: tank,
+            done: done,
+            lines: Vec::new(),
+        }
+    }
+
+    fn text(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let &(_, ref name, ref loadout) = match self.customers.last() {
+            Some(customer) => customer,
+            None => return lines,
+        };
+        lines.push(format!("{} has ${} to spend", name, loadout.money));
+        for (i, offer) in CATALOGUE.iter().enumerate() {
+            lines.push(format!("{} {} x{} for ${} (have {})",
+                               if i == self.selected { ">" } else { " " },
+                               offer.goods.name(),
+                               offer.quantity,
+                               offer.price,
+                               loadout.owned(offer.goods)));
+        }
+        lines.push(format!("{} done",
+                           if self.selected == CATALOGUE.len() { ">" } else { " " }));
+        lines
+    }
+}
+
+impl specs::System<Tick> for ShopSystem {
+    fn run(&mut self, arg: specs::RunArg, _: Tick) {
+        let (mut texts, mut drawables, dim) = arg.fetch(|w| {
+            (w.write::<Text>(),
+             w.write::<text::Drawable>(),
+             w.read_resource::<Dimensions>())
+        });
+
+        let choices = CATALOGUE.len() + 1;
+        for control in self.tank.try_iter() {
+            let step = match control {
+                TankControl::PowerIncreasing |
+                TankControl::PreviousWeapon => choices - 1,
+                TankControl::PowerDecreasing |
+                TankControl::NextWeapon => 1,
+                _ => 0,
+            };
+            self.selected = (self.selected + step) % choices;
+        }
+        for _ in self.fire.try_iter() {
+            let finished = match self.customers.last_mut() {
+                Some(&mut (_, ref name, ref mut loadout)) => {
+                    if self.selected == CATALOGUE.len() {
+                        true
+                    } else {
+                        let offer = &CATALOGUE[self.selected];
+                        if loadout.buy(offer) {
+                            debug!("{} bought {} {}", name, offer.quantity, offer.goods.name());
+                        } else {
+                            debug!("{} can't afford {}", name, offer.goods.name());
+                        }
+                        false
+                    }
+                }
+                None => false,
+            };
+            if finished {
+                let (number, _, loadout) = self.customers.pop().unwrap();
+                if self.done.send((number, loadout)).is_err() {
+                    warn!("Shop disconnected");
+                }
+                self.selected = 0;
+            }
+        }
+
+        let lines = self.text();
+        while self.lines.len() < lines.len() {
+            let line = arg.create_pure();
+            let y = dim.game_height() as f32 * 0.85 - self.lines.len() as f32 * LINE_SPACING;
+            texts.insert(line,
+                         Text {
+                             text: String::new(),
+                             screen_position: Point2::new(dim.game_width() as f32 * 0.2, y),
+                             scale: LINE_SCALE,
+                         });
+            drawables.insert(line, text::Drawable::new([1.0, 1.0, 1.0]));
+            self.lines.push(line);
+        }
+        for (i, &line) in self.lines.iter().enumerate() {
+            if let Some(text) = texts.get_mut(line) {
+                text.text = lines.get(i).cloned().unwrap_or_else(String::new);
+            }
+        }
+    }
+}
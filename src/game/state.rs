@@ -1,8 +1,12 @@
 use explosion::Explosion;
-use game::{Player, Players, QuitStatus};
+use game::{GameRng, Player, Players, QuitStatus, Statistics, label_text};
+use physics::{Position, Wind};
 use projectile::Projectile;
 use specs::{self, Join};
 use std::sync::mpsc;
+use tank::{self, BURN_DAMAGE, Effect, Falling, Status, Tank};
+use terrain::Terrain;
+use text::Text;
 
 #[derive(Debug)]
 pub struct ActivePlayer {
@@ -23,6 +27,7 @@ pub enum GameState {
     TankFiring,
     ProjectilesTravelling,
     ProjectilesImpacting,
+    TanksSettling,
     CalculateNextPlayer,
     GameOver,
 }
@@ -62,11 +67,17 @@ impl Turn {
     }
 }
 
+// Announces the turn number and player number of every turn as it starts
+pub type TurnLog = mpsc::Sender<(u32, u8)>;
+
 #[derive(Debug)]
 pub struct GameStateSystem {
     state: GameState,
     turn: Turn,
+    // Whose turn it is, until the turn is over
+    current: Option<Player>,
     result: mpsc::Sender<QuitStatus>,
+    turns: Option<TurnLog>,
 }
 
 impl<C> specs::System<C> for GameStateSystem {
@@ -76,6 +87,7 @@ impl<C> specs::System<C> for GameStateSystem {
             TankFiring => self.firing(arg),
             ProjectilesTravelling => self.projectiles(arg),
             ProjectilesImpacting => self.exploding(arg),
+            TanksSettling => self.settling(arg),
             CalculateNextPlayer => self.calculate_next(arg),
             GameOver => arg.fetch(|_| ()),
         }
@@ -83,42 +95,51 @@ impl<C> specs::System<C> for GameStateSystem {
 }
 
 impl GameStateSystem {
-    pub fn new() -> (GameStateSystem, mpsc::Receiver<QuitStatus>) {
+    pub fn new(turns: Option<TurnLog>) -> (GameStateSystem, mpsc::Receiver<QuitStatus>) {
         let (tx, rx) = mpsc::channel();
 
         (GameStateSystem {
              state: GameState::CalculateNextPlayer,
              turn: Turn::first(),
+             current: None,
              result: tx,
+             turns: turns,
          },
          rx)
     }
 
     fn calculate_next(&mut self, arg: specs::RunArg) {
         arg.fetch(|w| {
-            let players = w.read_resource_now::<Players>();
-            let players = players.get_remaining(w);
+            let all_players = w.read_resource_now::<Players>();
+            let players = all_players.get_remaining(w);
             if players.is_empty() {
                 self.state = GameState::GameOver;
                 info!("All players were destroyed!");
+                let scoreboard = w.read_resource_now::<Statistics>().scoreboard();
                 self.result
-                    .send(QuitStatus::Draw { turn: self.turn.number })
+                    .send(QuitStatus::Draw {
+                              turn: self.turn.number,
+                              scoreboard: scoreboard,
+                          })
                     .expect("Unable to send final status");
             } else if players.len() == 1 {
                 self.state = GameState::GameOver;
-                let player = players.first().unwrap().player_number();
-                info!("Player {} is the winner after {} turns!",
+                let winner = players.first().unwrap();
+                let player = winner.player_number();
+                info!("Player {} ({}) is the winner after {} turns!",
                       player,
+                      all_players.name(winner),
                       self.turn.number);
+                let scoreboard = w.read_resource_now::<Statistics>().scoreboard();
                 self.result
                     .send(QuitStatus::PlayerWon {
                               player: player,
                               turn: self.turn.number,
+                              scoreboard: scoreboard,
                           })
                     .expect("Unable to send final status");
             } else {
                 {
-                    use tank::Tank;
                     use specs::Gate;
                     let tanks = w.read::<Tank>().pass();
                     for &p in &players {
@@ -128,10 +149,35 @@ impl GameStateSystem {
                     }
                 }
                 let mut active = w.write_resource_now::<ActivePlayer>();
+                let turn_number = self.turn.number;
                 let next_tank = self.turn.next(players);
+                if self.turn.number != turn_number {
+                    let mut rng = w.write_resource_now::<GameRng>();
+                    let mut wind = w.write_resource_now::<Wind>();
+                    *wind = Wind::random(&mut *rng);
+                    info!("Wind for turn {} is {:.1}", self.turn.number, wind.speed);
+                }
+                if let Some(player) = next_tank {
+                    use specs::Gate;
+                    let statuses = w.read::<Status>().pass();
+                    if statuses.get(player.id()).map_or(false, |s| s.has(Effect::Stunned)) {
+                        info!("Player {} is stunned and misses their turn",
+                              player.player_number());
+                        // Their turn is over straight away, counting down the stun with it
+                        self.current = Some(player);
+                        self.state = GameState::TanksSettling;
+                        return;
+                    }
+                }
                 active.player = next_tank;
+                self.current = next_tank;
                 info!("Next tank to fire is {:?}", next_tank);
-                if next_tank.is_some() {
+                if let Some(player) = next_tank {
+                    if let Some(ref turns) = self.turns {
+                        if turns.send((self.turn.number, player.player_number())).is_err() {
+                            warn!("Turn announcements disconnected");
+                        }
+                    }
                     self.state = GameState::TankFiring;
                 } else {
                     warn!("Unable to determine next tank to fire");
@@ -152,7 +198,8 @@ impl GameStateSystem {
     }
 
     fn projectiles(&mut self, arg: specs::RunArg) {
-        // Once all projectiles are gone, move to next state
+        // Once all projectiles are gone, move to next state. Projectiles that split are
+        // replaced by their warheads in the same tick, so this waits for those too.
         let projectiles = arg.fetch(|w| w.read::<Projectile>());
         if (&projectiles.check()).join().next().is_none() {
             self.state = GameState::ProjectilesImpacting;
@@ -164,8 +211,52 @@ impl GameStateSystem {
         // Once all explosions are gone, move to next state
         let explosives = arg.fetch(|w| w.read::<Explosion>());
         if (&explosives.check()).join().next().is_none() {
+            self.state = GameState::TanksSettling;
+            debug!("Explosions are done, waiting for tanks to settle");
+        }
+    }
+
+    fn settling(&mut self, arg: specs::RunArg) {
+        // Once no tanks are left in the air, move to next state
+        let (falling, mut tanks, mut statuses, mut texts, positions, terrain, players, mut stats) =
+            arg.fetch(|w| {
+                          (w.read::<Falling>(),
+                           w.write::<Tank>(),
+                           w.write::<Status>(),
+                           w.write::<Text>(),
+                           w.read::<Position>(),
+                           w.read_resource::<Terrain>(),
+                           w.read_resource::<Players>(),
+                           w.write_resource::<Statistics>())
+                      });
+        let unsupported = (&tanks.check(), &positions)
+            .join()
+            .any(|(_, p)| tank::is_unsupported(p, &terrain));
+        if !unsupported && (&falling.check()).join().next().is_none() {
+            // Effects on the tank whose turn it was count down as its turn ends
+            if let Some(player) = self.current.take() {
+                let id = player.id();
+                if let (Some(t), Some(s)) = (tanks.get_mut(id), statuses.get_mut(id)) {
+                    if s.has(Effect::Burning) && t.health > 0.0 {
+                        let dealt = t.take_damage(BURN_DAMAGE);
+                        info!("Player {} is burning, health: {}", player.player_number(), t.health);
+                        let destroyed = t.health <= 0.0;
+                        if destroyed {
+                            info!("Tank destroyed!");
+                            arg.delete(id);
+                        }
+                        stats.effect_damage(s.source(Effect::Burning), &player, dealt, destroyed);
+                    }
+                    for effect in s.end_turn() {
+                        debug!("Player {} is no longer {}", player.player_number(), effect.name());
+                    }
+                    if let Some(text) = texts.get_mut(id) {
+                        text.text = label_text(players.name(&player), t, Some(s));
+                    }
+                }
+            }
             self.state = GameState::CalculateNextPlayer;
-            debug!("Explosions are done, waiting for next tank to be determined");
+            debug!("Tanks have settled, waiting for next tank to be determined");
         }
     }
 }
@@ -0,0 +1,242 @@
+use game::Player;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::SplitWhitespace;
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct PlayerStats {
+    pub player: u8,
+    pub shots_fired: u32,
+    pub hits: u32,
+    pub damage_dealt: f32,
+    pub damage_received: f32,
+    pub kills: u32,
+    // 1 for the first tank destroyed, 2 for the next and so on. Survivors have none.
+    pub eliminated: Option<u32>,
+}
+
+impl PlayerStats {
+    fn new(player: u8) -> PlayerStats {
+        PlayerStats {
+            player: player,
+            shots_fired: 0,
+            hits: 0,
+            damage_dealt: 0.0,
+            damage_received: 0.0,
+            kills: 0,
+            eliminated: None,
+        }
+    }
+
+    // Written as "<player>:<shots>:<hits>:<dealt>:<received>:<kills>:<eliminated>",
+    // with 0 for survivors
+    fn format(&self) -> String {
+        format!("{}:{}:{}:{}:{}:{}:{}",
+                self.player,
+                self.shots_fired,
+                self.hits,
+                self.damage_dealt,
+                self.damage_received,
+                self.kills,
+                self.eliminated.unwrap_or(0))
+    }
+
+    fn parse(word: &str) -> Option<PlayerStats> {
+        let fields: Vec<&str> = word.split(':').collect();
+        if fields.len() != 7 {
+            return None;
+        }
+        match (fields[0].parse(),
+               fields[1].parse(),
+               fields[2].parse(),
+               fields[3].parse(),
+               fields[4].parse(),
+               fields[5].parse(),
+               fields[6].parse()) {
+            (Ok(player), Ok(shots), Ok(hits), Ok(dealt), Ok(received), Ok(kills), Ok(order)) => {
+                Some(PlayerStats {
+                         player: player,
+                         shots_fired: shots,
+                         hits: hits,
+                         damage_dealt: dealt,
+                         damage_received: received,
+                         kills: kills,
+                         eliminated: if order == 0 { None } else { Some(order) },
+                     })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PlayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "Player {}: {} shots, {} hits, {:.0} damage dealt, {:.0} taken, {} kills",
+               self.player,
+               self.shots_fired,
+               self.hits,
+               self.damage_dealt,
+               self.damage_received,
+               self.kills)?;
+        match self.eliminated {
+            Some(order) => write!(f, ", eliminated #{}", order),
+            None => write!(f, ", survived"),
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Scoreboard {
+    pub players: Vec<PlayerStats>,
+}
+
+impl Scoreboard {
+    pub fn format(&self) -> String {
+        let players: Vec<String> = self.players.iter().map(|s| s.format()).collect();
+        players.join(" ")
+    }
+
+    // Reads the stats of every player up to the end of the line
+    pub fn parse(words: &mut SplitWhitespace) -> Option<Scoreboard> {
+        let mut players = Vec::new();
+        for word in words {
+            match PlayerStats::parse(word) {
+                Some(stats) => players.push(stats),
+                None => return None,
+            }
+        }
+        Some(Scoreboard { players: players })
+    }
+}
+
+// A shell fired by a player. Warheads split off from it are part of the same shot.
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub struct Shot {
+    pub player: Player,
+    id: u32,
+}
+
+// Kept up to date by the systems that fire shells and deal damage
+#[derive(Debug)]
+pub struct Statistics {
+    scoreboard: Scoreboard,
+    eliminations: u32,
+    shots: u32,
+    // Shot ids with the number of each player they have already hit
+    hit: HashSet<(u32, u8)>,
+    // Who last damaged each player, by player number
+    attackers: HashMap<u8, Player>,
+}
+
+impl Statistics {
+    pub fn new(players: &[Player]) -> Statistics {
+        Statistics {
+            scoreboard: Scoreboard {
+                players: players
+                    .iter()
+                    .map(|p| PlayerStats::new(p.player_number()))
+                    .collect(),
+            },
+            eliminations: 0,
+            shots: 0,
+            hit: HashSet::new(),
+            attackers: HashMap::new(),
+        }
+    }
+
+    fn stats(&self, player: &Player) -> Option<&PlayerStats> {
+        self.scoreboard
+            .players
+            .iter()
+            .find(|s| s.player == player.player_number())
+    }
+
+    fn stats_mut(&mut self, player: &Player) -> Option<&mut PlayerStats> {
+        self.scoreboard
+            .players
+            .iter_mut()
+            .find(|s| s.player == player.player_number())
+    }
+
+    pub fn shot_fired(&mut self, player: &Player) -> Shot {
+        if let Some(stats) = self.stats_mut(player) {
+            stats.shots_fired += 1;
+        }
+        self.shots += 1;
+        Shot {
+            player: *player,
+            id: self.shots,
+        }
+    }
+
+    // A shot only counts as one hit on each tank, however many times its shell, warheads and
+    // explosions damage it. Hitting yourself, or doing no damage, doesn't count.
+    pub fn shell_damage(&mut self,
+                        shot: Option<Shot>,
+                        target: &Player,
+                        amount: f32,
+                        destroyed: bool) {
+        if let Some(shot) = shot {
+            if amount > 0.0 && shot.player != *target &&
+               self.hit.insert((shot.id, target.player_number())) {
+                if let Some(stats) = self.stats_mut(&shot.player) {
+                    stats.hits += 1;
+                }
+            }
+        }
+        self.credit(shot.map(|s| s.player), target, amount, destroyed);
+    }
+
+    // Damage from a lasting effect, such as burning, goes to whoever caused it without
+    // counting as another hit
+    pub fn effect_damage(&mut self,
+                         source: Option<Player>,
+                         target: &Player,
+                         amount: f32,
+                         destroyed: bool) {
+        self.credit(source, target, amount, destroyed);
+    }
+
+    // Damage from the terrain, such as a tank falling, which nobody is credited with
+    pub fn damage(&mut self, target: &Player, amount: f32, destroyed: bool) {
+        self.credit(None, target, amount, destroyed);
+    }
+
+    // Hurting yourself doesn't count as a kill
+    fn credit(&mut self, shooter: Option<Player>, target: &Player, amount: f32, destroyed: bool) {
+        if let Some(stats) = self.stats_mut(target) {
+            stats.damage_received += amount;
+        }
+        if destroyed {
+            self.eliminations += 1;
+            let order = self.eliminations;
+            if let Some(stats) = self.stats_mut(target) {
+                stats.eliminated = Some(order);
+            }
+        }
+        let shooter = match shooter {
+            Some(s) if s != *target => s,
+            _ => return,
+        };
+        self.attackers.insert(target.player_number(), shooter);
+        if let Some(stats) = self.stats_mut(&shooter) {
+            stats.damage_dealt += amount;
+            if destroyed {
+                stats.kills += 1;
+            }
+        }
+    }
+
+    pub fn damage_dealt(&self, player: &Player) -> f32 {
+        self.stats(player).map_or(0.0, |s| s.damage_dealt)
+    }
+
+    pub fn last_attacker(&self, player: &Player) -> Option<Player> {
+        self.attackers.get(&player.player_number()).cloned()
+    }
+
+    pub fn scoreboard(&self) -> Scoreboard {
+        self.scoreboard.clone()
+    }
+}
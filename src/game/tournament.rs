@@ -0,0 +1,102 @@
+use game::{Controller, MatchConfig, QuitStatus};
+
+const WIN_POINTS: u32 = 10;
+const KILL_POINTS: u32 = 3;
+// Damage dealt to other tanks is worth a point for every this much
+const DAMAGE_PER_POINT: f32 = 25.0;
+
+#[derive(Debug,Clone)]
+pub struct Standing {
+    pub player: u8,
+    pub name: String,
+    pub points: u32,
+    pub wins: u32,
+    pub kills: u32,
+    pub damage: f32,
+}
+
+// Plays the same roster over a number of rounds, each on fresh terrain, and keeps the score
+#[derive(Debug)]
+pub struct Tournament {
+    rounds: u32,
+    played: u32,
+    standings: Vec<Standing>,
+}
+
+impl Tournament {
+    pub fn new(rounds: u32, config: &MatchConfig) -> Tournament {
+        let standings = config
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|&(_, slot)| slot.controller != Controller::Vacant)
+            .map(|(i, slot)| {
+                     Standing {
+                         player: (i as u8) + 1,
+                         name: slot.name.clone(),
+                         points: 0,
+                         wins: 0,
+                         kills: 0,
+                         damage: 0.0,
+                     }
+                 })
+            .collect();
+        Tournament {
+            rounds: rounds,
+            played: 0,
+            standings: standings,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.played >= self.rounds
+    }
+
+    // Quit matches don't count
+    pub fn record(&mut self, result: &QuitStatus) {
+        let scoreboard = match result.scoreboard() {
+            Some(scoreboard) => scoreboard,
+            None => return,
+        };
+        self.played += 1;
+        for stats in &scoreboard.players {
+            if let Some(standing) = self.standings.iter_mut().find(|s| s.player == stats.player) {
+                standing.kills += stats.kills;
+                standing.damage += stats.damage_dealt;
+                standing.points += stats.kills * KILL_POINTS +
+                                   (stats.damage_dealt / DAMAGE_PER_POINT) as u32;
+            }
+        }
+        if let QuitStatus::PlayerWon { player, .. } = *result {
+            if let Some(standing) = self.standings.iter_mut().find(|s| s.player == player) {
+                standing.wins += 1;
+                standing.points += WIN_POINTS;
+            }
+        }
+    }
+
+    // Highest points first, with ties going to the player with more wins
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings = self.standings.clone();
+        standings.sort_by(|a, b| (b.points, b.wins, a.player).cmp(&(a.points, a.wins, b.player)));
+        standings
+    }
+
+    pub fn log_standings(&self) {
+        if self.is_over() {
+            info!("Final standings after {} rounds:", self.played);
+        } else {
+            info!("Standings after round {} of {}:", self.played, self.rounds);
+        }
+        for (place, s) in self.standings().iter().enumerate() {
+            info!("{}. {} (player {}): {} points, {} wins, {} kills, {:.0} damage",
+                  place + 1,
+                  s.name,
+                  s.player,
+                  s.points,
+                  s.wins,
+                  s.kills,
+                  s.damage);
+        }
+    }
+}
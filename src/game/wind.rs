@@ -0,0 +1,70 @@
+use cgmath::Point2;
+use physics::{Dimensions, Tick, Wind};
+use specs;
+use text::{self, Text};
+
+// Below this the wind is shown as calm
+const CALM: f32 = 0.5;
+
+// Keeps a label at the top of the screen up to date with the current wind
+#[derive(Debug)]
+pub struct WindDisplaySystem {
+    label: Option<specs::Entity>,
+    shown: Option<Wind>,
+}
+
+impl WindDisplaySystem {
+    pub fn new() -> WindDisplaySystem {
+        WindDisplaySystem {
+            label: None,
+            shown: None,
+        }
+    }
+}
+
+fn describe(wind: &Wind) -> String {
+    if wind.speed.abs() < CALM {
+        "Wind: calm".to_string()
+    } else if wind.speed > 0.0 {
+        format!("Wind: {:.0} >", wind.speed)
+    } else {
+        format!("Wind: < {:.0}", -wind.speed)
+    }
+}
+
+impl specs::System<Tick> for WindDisplaySystem {
+    fn run(&mut self, arg: specs::RunArg, _: Tick) {
+        let (wind, dim, mut texts, mut drawables) = arg.fetch(|w| {
+            (w.read_resource::<Wind>(),
+             w.read_resource::<Dimensions>(),
+             w.write::<Text>(),
+             w.write::<text::Drawable>())
+        });
+        if self.shown == Some(*wind) {
+            return;
+        }
+        self.shown = Some(*wind);
+
+        let description = describe(&wind);
+        match self.label {
+            Some(label) => {
+                if let Some(text) = texts.get_mut(label) {
+                    text.text = description;
+                }
+            }
+            None => {
+                let position = Point2::new(dim.game_width() as f32 / 2.0 - 50.0,
+                                           dim.game_height() as f32 * 0.95);
+                let label = arg.create_pure();
+                texts.insert(label,
+                             Text {
+                                 text: description,
+                                 screen_position: position,
+                                 scale: 0.5,
+                             });
+                drawables.insert(label, text::Drawable::new([1.0, 1.0, 1.0]));
+                self.label = Some(label);
+            }
+        }
+    }
+}
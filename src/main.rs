@@ -16,8 +16,10 @@ extern crate lazy_static;
 mod draw;
 mod explosion;
 mod game;
+mod options;
 mod physics;
 mod projectile;
+mod shield;
 mod tank;
 mod terrain;
 mod text;
@@ -29,19 +31,181 @@ fn main() {
     configure_logging();
     debug!("Starting up....");
 
+    let mut options = options::Options::from_args();
+    let mut config = match_config(&options);
+    let mut tournament = config
+        .rounds
+        .map(|rounds| game::Tournament::new(rounds, &config));
+    if options.headless {
+        loop {
+            // Each match gets its own frame limit
+            let mut window = draw::HeadlessWindow::new(options.frame_limit);
+            let session = next_session(&mut options, &mut config);
+            // Outside of tournaments, headless runs play a single match
+            let (expected, single_match) = match session {
+                game::Session::Replay(ref replay) => (replay.result.clone(), true),
+                game::Session::Play { .. } => (None, tournament.is_none()),
+                _ => (None, true),
+            };
+            let result = game::run_headless(&mut window, session);
+            info!("Headless match finished: {:?}", result);
+            log_scoreboard(&result);
+            if result == Quit || expected.map_or(false, |e| e != result) {
+                std::process::exit(1);
+            }
+            if !play_on(&mut tournament, &result, single_match) {
+                break;
+            }
+            game::shop_headless(&mut config, &result);
+        }
+        return;
+    }
+
     let mut window = draw::GlutinWindow::new();
     loop {
-        match game::run(&mut window) {
+        let session = next_session(&mut options, &mut config);
+        // Replays and network matches are only played once
+        let single_match = match session {
+            game::Session::Play { .. } => false,
+            _ => true,
+        };
+        let result = game::run(&mut window, session);
+        match result {
             Quit => {
                 info!("Game was quit");
+                if let Some(ref tournament) = tournament {
+                    tournament.log_standings();
+                }
                 break;
             }
-            PlayerWon { player, turn } => info!("Player {} won on turn {}", player, turn),
-            Draw { turn } => info!("Draw on turn {}", turn),
+            PlayerWon { player, turn, .. } => info!("Player {} won on turn {}", player, turn),
+            Draw { turn, .. } => info!("Draw on turn {}", turn),
+        }
+        log_scoreboard(&result);
+        if !play_on(&mut tournament, &result, single_match) {
+            break;
+        }
+        if !game::shop(&mut window, &mut config, &result) {
+            info!("Game was quit in the shop");
+            break;
         }
     }
 }
 
+// Counts the result towards any tournament, and decides whether another match follows
+fn play_on(tournament: &mut Option<game::Tournament>,
+           result: &game::QuitStatus,
+           single_match: bool)
+           -> bool {
+    match *tournament {
+        Some(ref mut tournament) if !single_match => {
+            tournament.record(result);
+            tournament.log_standings();
+            !tournament.is_over()
+        }
+        _ => !single_match,
+    }
+}
+
+fn log_scoreboard(result: &game::QuitStatus) {
+    if let Some(scoreboard) = result.scoreboard() {
+        for stats in &scoreboard.players {
+            info!("{}", stats);
+        }
+    }
+}
+
+fn match_config(options: &options::Options) -> game::MatchConfig {
+    let mut config = match options.config {
+        Some(ref path) => {
+            match game::MatchConfig::load(path) {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("Unable to load match config {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => game::MatchConfig::new(),
+    };
+    if let Some(players) = options.players {
+        config.set_player_count(players);
+    }
+    if options.rounds.is_some() {
+        config.rounds = options.rounds;
+    }
+    for &(number, ref controller) in &options.controllers {
+        match (config.slot_mut(number), game::Controller::from_name(controller)) {
+            (Some(slot), Some(controller)) => slot.controller = controller,
+            _ => warn!("Ignoring invalid player {}={}", number, controller),
+        }
+    }
+    for &(number, ref name) in &options.names {
+        match config.slot_mut(number) {
+            Some(slot) => slot.set_name(name),
+            None => warn!("Ignoring name for unknown player {}", number),
+        }
+    }
+    config
+}
+
+// Only the first match uses the requested seed, replay, recording or network game.
+// Later ones get a fresh battlefield.
+fn next_session(options: &mut options::Options,
+                config: &mut game::MatchConfig)
+                -> game::Session {
+    if let Some(path) = options.replay.take() {
+        match game::Replay::load(&path) {
+            Ok(replay) => return game::Session::Replay(replay),
+            Err(e) => {
+                error!("Unable to load replay {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(address) = options.join.take() {
+        info!("Joining match at {}", address);
+        match game::NetClient::connect(address.as_str()) {
+            Ok(client) => return game::Session::Join(client),
+            Err(e) => {
+                error!("Unable to join match at {}: {}", address, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    let seed = options
+        .seed
+        .take()
+        .map(game::GameSeed)
+        .or_else(|| config.seed.take())
+        .unwrap_or_else(game::GameSeed::random);
+    if let Some(address) = options.host.take() {
+        if options.record.take().is_some() {
+            warn!("Network matches can't be recorded");
+        }
+        let host = game::NetHost::bind(address.as_str())
+            .and_then(|listener| game::NetHost::accept_players(&listener, config));
+        match host {
+            Ok(host) => {
+                return game::Session::Host {
+                           seed: seed,
+                           config: config.clone(),
+                           host: host,
+                       }
+            }
+            Err(e) => {
+                error!("Unable to host match at {}: {}", address, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    game::Session::Play {
+        seed: seed,
+        config: config.clone(),
+        recording: options.record.take(),
+    }
+}
+
 fn configure_logging() {
     use log::LogLevelFilter;
     use log4rs::append::console::ConsoleAppender;
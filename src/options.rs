@@ -0,0 +1,115 @@
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct Options {
+    pub headless: bool,
+    pub frame_limit: Option<u32>,
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub config: Option<PathBuf>,
+    pub host: Option<String>,
+    pub join: Option<String>,
+    pub players: Option<usize>,
+    pub rounds: Option<u32>,
+    pub controllers: Vec<(usize, String)>,
+    pub names: Vec<(usize, String)>,
+}
+
+impl Options {
+    pub fn from_args() -> Options {
+        let mut options = Options {
+            headless: false,
+            frame_limit: None,
+            seed: None,
+            record: None,
+            replay: None,
+            config: None,
+            host: None,
+            join: None,
+            players: None,
+            rounds: None,
+            controllers: Vec::new(),
+            names: Vec::new(),
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--frame-limit" => options.frame_limit = parse_value(&arg, args.next()),
+                "--seed" => options.seed = parse_value(&arg, args.next()),
+                "--record" => options.record = path_value(&arg, args.next()),
+                "--replay" => options.replay = path_value(&arg, args.next()),
+                "--config" => options.config = path_value(&arg, args.next()),
+                "--host" => options.host = string_value(&arg, args.next()),
+                "--join" => options.join = string_value(&arg, args.next()),
+                "--players" => options.players = parse_value(&arg, args.next()),
+                "--rounds" => options.rounds = parse_value(&arg, args.next()),
+                "--player" => {
+                    if let Some(v) = slot_value(&arg, args.next()) {
+                        options.controllers.push(v);
+                    }
+                }
+                "--name" => {
+                    if let Some(v) = slot_value(&arg, args.next()) {
+                        options.names.push(v);
+                    }
+                }
+                _ => warn!("Ignoring unknown argument {}", arg),
+            }
+        }
+        options
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: Option<String>) -> Option<T> {
+    match value {
+        Some(v) => {
+            match v.parse() {
+                Ok(v) => Some(v),
+                Err(_) => {
+                    warn!("Ignoring invalid value {} for {}", v, name);
+                    None
+                }
+            }
+        }
+        None => {
+            warn!("Missing value for {}", name);
+            None
+        }
+    }
+}
+
+fn path_value(name: &str, value: Option<String>) -> Option<PathBuf> {
+    string_value(name, value).map(PathBuf::from)
+}
+
+fn string_value(name: &str, value: Option<String>) -> Option<String> {
+    if value.is_none() {
+        warn!("Missing value for {}", name);
+    }
+    value
+}
+
+// Values of the form <slot>=<value>, e.g. "--player 2=ai:hard"
+fn slot_value(name: &str, value: Option<String>) -> Option<(usize, String)> {
+    let value = match value {
+        Some(v) => v,
+        None => {
+            warn!("Missing value for {}", name);
+            return None;
+        }
+    };
+    let mut parts = value.splitn(2, '=');
+    match (parts.next().and_then(|s| s.parse().ok()), parts.next()) {
+        (Some(slot), Some(v)) => Some((slot, v.to_string())),
+        _ => {
+            warn!("Ignoring invalid value {} for {}, expected <slot>=<value>",
+                  value,
+                  name);
+            None
+        }
+    }
+}
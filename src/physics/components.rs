@@ -84,3 +84,12 @@ pub struct Mass {
 impl specs::Component for Mass {
     type Storage = specs::VecStorage<Mass>;
 }
+
+#[derive(Debug)]
+pub struct Drag {
+    pub coefficient: f32,
+}
+
+impl specs::Component for Drag {
+    type Storage = specs::HashMapStorage<Drag>;
+}
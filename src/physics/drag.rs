@@ -0,0 +1,29 @@
+use cgmath::Vector2;
+use cgmath::prelude::*;
+use specs::{self, Join};
+use super::{Drag, Mass, Tick, Velocity};
+
+// Slows the velocity by coefficient * speed^2 / mass, never reversing it
+pub fn apply_drag(velocity: &mut Vector2<f32>, coefficient: f32, mass: f32, delta: f32) {
+    let slowdown = (coefficient * velocity.magnitude() / mass * delta).min(1.0);
+    *velocity = *velocity * (1.0 - slowdown);
+}
+
+#[derive(Debug)]
+pub struct DragSystem;
+
+impl DragSystem {
+    pub fn new() -> DragSystem {
+        DragSystem {}
+    }
+}
+
+impl specs::System<Tick> for DragSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
+        let (drag, mass, mut velocities) =
+            arg.fetch(|w| (w.read::<Drag>(), w.read::<Mass>(), w.write::<Velocity>()));
+        for (d, m, v) in (&drag, &mass, &mut velocities).join() {
+            apply_drag(&mut v.velocity, d.coefficient, m.mass, tick.delta);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Delta, Mass, Velocity};
+use super::{Mass, Tick, Velocity};
 use specs;
 
 pub const GRAVITY: f32 = -0.98;
@@ -12,13 +12,13 @@ impl GravitySystem {
     }
 }
 
-impl specs::System<Delta> for GravitySystem {
-    fn run(&mut self, arg: specs::RunArg, time: Delta) {
+impl specs::System<Tick> for GravitySystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
         use specs::Join;
         let (mass, mut velocities) = arg.fetch(|w| (w.read::<Mass>(), w.write::<Velocity>()));
         for (m, v) in (&mass, &mut velocities).join() {
             let acc = m.mass * GRAVITY;
-            v.velocity.y += acc * time;
+            v.velocity.y += acc * tick.delta;
         }
     }
 }
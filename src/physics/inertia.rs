@@ -1,4 +1,4 @@
-use super::{Delta, Position, Velocity};
+use super::{Position, Tick, Velocity};
 use cgmath::prelude::*;
 use specs;
 
@@ -11,14 +11,14 @@ impl InertiaSystem {
     }
 }
 
-impl specs::System<Delta> for InertiaSystem {
-    fn run(&mut self, arg: specs::RunArg, time: Delta) {
+impl specs::System<Tick> for InertiaSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
         use specs::Join;
         let (mut positions, mut velocities) =
             arg.fetch(|w| (w.write::<Position>(), w.write::<Velocity>()));
         for (p, v) in (&mut positions, &mut velocities).join() {
-            p.position += v.velocity * time;
-            p.orient = (p.orient + (v.angular_velocity * time)).normalize();
+            p.position += v.velocity * tick.delta;
+            p.orient = (p.orient + (v.angular_velocity * tick.delta)).normalize();
         }
     }
 }
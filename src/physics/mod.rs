@@ -1,12 +1,18 @@
 use specs;
 
 mod components;
+mod drag;
 mod inertia;
 mod gravity;
+mod tick;
+mod wind;
 
 pub type Delta = f32;
-pub type Planner = specs::Planner<Delta>;
+pub type Planner = specs::Planner<Tick>;
 
 pub use self::components::*;
+pub use self::drag::{DragSystem, apply_drag};
 pub use self::inertia::InertiaSystem;
 pub use self::gravity::{GRAVITY, GravitySystem};
+pub use self::tick::{TICK_DELTA, Tick};
+pub use self::wind::{Wind, WindSystem};
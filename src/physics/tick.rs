@@ -0,0 +1,12 @@
+use physics::Delta;
+
+pub const TICK_DELTA: Delta = 1.0 / 60.0;
+
+// Context passed to every system on each fixed simulation step. Only the last
+// step of a frame is rendered.
+#[derive(Debug,Copy,Clone)]
+pub struct Tick {
+    pub number: u64,
+    pub delta: Delta,
+    pub render: bool,
+}
@@ -0,0 +1,52 @@
+use rand::Rng;
+use specs::{self, Join};
+use super::{Drag, Mass, Tick, Velocity};
+
+const MAX_WIND: f32 = 25.0;
+// Shells of this mass are accelerated by exactly the wind speed, heavier ones less
+const REFERENCE_MASS: f32 = 75.0;
+
+// Positive wind blows towards increasing x
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub struct Wind {
+    pub speed: f32,
+}
+
+impl Wind {
+    pub fn calm() -> Wind {
+        Wind { speed: 0.0 }
+    }
+
+    pub fn random<R: Rng>(rng: &mut R) -> Wind {
+        Wind { speed: rng.gen_range(-MAX_WIND, MAX_WIND) }
+    }
+
+    // Horizontal acceleration of something with the given mass
+    pub fn acceleration(&self, mass: f32) -> f32 {
+        self.speed * REFERENCE_MASS / mass
+    }
+}
+
+#[derive(Debug)]
+pub struct WindSystem;
+
+impl WindSystem {
+    pub fn new() -> WindSystem {
+        WindSystem {}
+    }
+}
+
+impl specs::System<Tick> for WindSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
+        let (wind, drag, mass, mut velocities) = arg.fetch(|w| {
+            (w.read_resource::<Wind>(),
+             w.read::<Drag>(),
+             w.read::<Mass>(),
+             w.write::<Velocity>())
+        });
+        // Only bodies the air has a hold on are blown about, which leaves out falling tanks
+        for (_, m, v) in (&drag, &mass, &mut velocities).join() {
+            v.velocity.x += wind.acceleration(m.mass) * tick.delta;
+        }
+    }
+}
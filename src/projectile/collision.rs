@@ -1,11 +1,17 @@
-use cgmath::Deg;
+use cgmath::{Deg, Vector2};
 use cgmath::prelude::*;
 use explosion;
-use physics::{Dimensions, Position};
-use projectile::Projectile;
+use game::{Players, Statistics};
+use physics::{Dimensions, Mass, Position, Velocity};
+use projectile::{Behaviour, Digging, Projectile, Rolling};
+use shield::Shield;
 use specs;
+use tank::{self, Status, Tank};
 use terrain::Terrain;
 
+// Extra damage dealt to a tank hit directly, as a fraction of the weapon's damage
+const DIRECT_HIT_BONUS: f32 = 0.5;
+
 #[derive(Debug)]
 pub struct CollisionSystem;
 
@@ -18,12 +24,29 @@ impl CollisionSystem {
 enum Collision {
     None,
     OutOfBounds,
+    Tank(specs::Entity),
     Terrain,
 }
-fn check_collision(p: &Position, dim: &Dimensions, terrain: &Terrain) -> Collision {
+fn touches(hull: &Position, p: &Position) -> bool {
+    tank::hull_contains(hull, p.position, p.scale / 2.0)
+}
+
+// Hits on the ignored tank don't count, so that shells can leave the barrel
+fn check_collision(p: &Position,
+                   ignored: Option<specs::Entity>,
+                   hulls: &[(specs::Entity, Position)],
+                   dim: &Dimensions,
+                   terrain: &Terrain)
+                   -> Collision {
+    let hit = hulls
+        .iter()
+        .find(|&&(e, ref hull)| Some(e) != ignored && touches(hull, p));
     if p.position.x < 0.0 || p.position.x > (dim.game_width() as f32) || p.position.y < 0.0 {
         info!("Projectile went out of bounds at {:?}", p.position);
         Collision::OutOfBounds
+    } else if let Some(&(e, _)) = hit {
+        info!("Projectile hit a tank directly at {:?}", p.position);
+        Collision::Tank(e)
     } else if terrain.get_height(p.position.x) > p.position.y {
         info!("Projectile went into terrain at {:?}", p.position);
         Collision::Terrain
@@ -35,32 +58,168 @@ fn check_collision(p: &Position, dim: &Dimensions, terrain: &Terrain) -> Collisi
 impl<C> specs::System<C> for CollisionSystem {
     fn run(&mut self, arg: specs::RunArg, _: C) {
         use specs::Join;
-        let (mut positions, projectiles, mut explosives, mut drawables, dim, entities, terrain) =
-            arg.fetch(|w| {
-                (w.write::<Position>(),
-                 w.read::<Projectile>(),
-                 w.write::<explosion::Explosion>(),
-                 w.write::<explosion::Drawable>(),
-                 w.read_resource::<Dimensions>(),
-                 w.entities(),
-                 w.read_resource::<Terrain>())
-            });
+        let (mut positions,
+             mut velocities,
+             mut masses,
+             mut rolling,
+             mut digging,
+             mut projectiles,
+             mut tanks,
+             statuses,
+             mut shields,
+             mut explosives,
+             mut drawables,
+             dim,
+             players,
+             mut stats,
+             entities,
+             terrain) = arg.fetch(|w| {
+            (w.write::<Position>(),
+             w.write::<Velocity>(),
+             w.write::<Mass>(),
+             w.write::<Rolling>(),
+             w.write::<Digging>(),
+             w.write::<Projectile>(),
+             w.write::<Tank>(),
+             w.read::<Status>(),
+             w.write::<Shield>(),
+             w.write::<explosion::Explosion>(),
+             w.write::<explosion::Drawable>(),
+             w.read_resource::<Dimensions>(),
+             w.read_resource::<Players>(),
+             w.write_resource::<Statistics>(),
+             w.entities(),
+             w.read_resource::<Terrain>())
+        });
+
+        let hulls: Vec<(specs::Entity, Position)> = (&tanks.check(), &positions, &entities)
+            .join()
+            .map(|(_, p, e)| (e, p.clone()))
+            .collect();
 
         let mut to_create = Vec::new();
-        for (_, p, e) in (&projectiles.check(), &positions, &entities).join() {
-            match check_collision(p, &dim, &terrain) {
+        let mut to_roll = Vec::new();
+        let mut to_dig = Vec::new();
+        let mut direct_hits = Vec::new();
+        let mut deflected = Vec::new();
+        for (projectile, p, e) in (&mut projectiles, &positions, &entities).join() {
+            // Projectiles on the ground are moved by their own systems until they are done
+            let finished = match (rolling.get(e), digging.get(e)) {
+                (Some(r), _) => Some(r.finished),
+                (_, Some(d)) => Some(d.finished),
+                _ => None,
+            };
+            if let Some(finished) = finished {
+                if finished {
+                    arg.delete(e);
+                    to_create.push((p.position, projectile.weapon, projectile.shot));
+                }
+                continue;
+            }
+            // Shields bounce incoming shells away before they can reach the hull
+            let deflection = velocities.get(e).and_then(|v| {
+                (&shields, &positions, &entities)
+                    .join()
+                    .filter_map(|(s, t, id)| {
+                                    s.deflect(t.position, p.position, v.velocity)
+                                        .map(|bounce| (id, bounce))
+                                })
+                    .next()
+            });
+            if let Some((tank, bounce)) = deflection {
+                info!("Projectile deflected by a shield at {:?}", p.position);
+                deflected.push((e, tank, bounce, projectile.weapon.damage * DIRECT_HIT_BONUS));
+                continue;
+            }
+            // Once clear of the tank that fired it, a shell can come back down on it
+            let owner = projectile.owner().map(|o| o.id());
+            if !projectile.armed {
+                projectile.armed = hulls
+                    .iter()
+                    .find(|&&(t, _)| Some(t) == owner)
+                    .map_or(true, |&(_, ref hull)| !touches(hull, p));
+            }
+            let ignored = if projectile.armed { None } else { owner };
+            match check_collision(p, ignored, &hulls, &dim, &terrain) {
                 Collision::None => (),
                 Collision::OutOfBounds => arg.delete(e),
-                Collision::Terrain => {
+                Collision::Tank(target) => {
                     arg.delete(e);
-                    to_create.push(p.position);
+                    to_create.push((p.position, projectile.weapon, projectile.shot));
+                    direct_hits.push((target,
+                                      projectile.shot,
+                                      projectile.weapon.damage * DIRECT_HIT_BONUS));
+                }
+                Collision::Terrain => {
+                    match projectile.weapon.behaviour {
+                        Behaviour::Roll => {
+                            // Roll off downhill, keeping any speed already going that way
+                            let downhill = terrain.get_normal_dir(p.position.x).sin();
+                            let direction = if downhill < 0.0 { -1.0 } else { 1.0 };
+                            let speed = velocities
+                                .get(e)
+                                .map_or(0.0, |v| v.velocity.x * direction);
+                            to_roll.push((e, direction, speed));
+                        }
+                        Behaviour::Dig { distance } => {
+                            let heading = velocities
+                                .get(e)
+                                .map_or(Vector2::new(0.0, -1.0), |v| v.velocity.normalize());
+                            to_dig.push((e, heading, distance));
+                        }
+                        _ => {
+                            arg.delete(e);
+                            to_create.push((p.position, projectile.weapon, projectile.shot));
+                        }
+                    }
+                }
+            }
+        }
+        for (e, tank, bounce, strain) in deflected {
+            if let Some(v) = velocities.get_mut(e) {
+                v.velocity = bounce;
+            }
+            if let Some(s) = shields.get_mut(tank) {
+                s.absorb(strain);
+                debug!("Shield deflected a shell, strength: {}", s.strength);
+            }
+        }
+        for (id, shot, damage) in direct_hits {
+            if let Some(t) = tanks.get_mut(id) {
+                if t.health <= 0.0 {
+                    continue;
+                }
+                let damage = damage * statuses.get(id).map_or(1.0, |s| s.damage_scale());
+                let dealt = t.take_damage(damage);
+                debug!("Tank took a direct hit, health: {}", t.health);
+                let destroyed = t.health <= 0.0;
+                if destroyed {
+                    info!("Tank destroyed!");
+                    arg.delete(id);
+                }
+                if let Some(target) = players.by_tank(id) {
+                    stats.shell_damage(shot, &target, dealt, destroyed);
                 }
             }
         }
-        for p in to_create {
+        for (e, direction, speed) in to_roll {
+            velocities.remove(e);
+            masses.remove(e);
+            rolling.insert(e, Rolling::new(direction, speed));
+            if let Some(p) = positions.get_mut(e) {
+                p.position.y = terrain.get_height(p.position.x);
+            }
+        }
+        for (e, heading, distance) in to_dig {
+            velocities.remove(e);
+            masses.remove(e);
+            digging.insert(e, Digging::new(heading, distance));
+        }
+        for (p, weapon, shot) in to_create {
             let id = arg.create_pure();
-            positions.insert(id, Position::new(p.x, p.y, Deg::zero(), 50.0));
-            explosives.insert(id, explosion::Explosion::new());
+            positions.insert(id,
+                             Position::new(p.x, p.y, Deg::zero(), weapon.explosion_radius));
+            explosives.insert(id, explosion::Explosion::new(&weapon, shot));
             drawables.insert(id, explosion::Drawable::new());
         }
     }
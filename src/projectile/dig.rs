@@ -0,0 +1,85 @@
+use cgmath::{Point2, Vector2};
+use cgmath::prelude::*;
+use physics::*;
+use projectile::Projectile;
+use specs::{self, Join};
+use terrain::Terrain;
+
+const DIG_SPEED: f32 = 60.0;
+// The tunnel is a little wider than the shell itself
+const TUNNEL_SCALE: f32 = 1.5;
+
+// A projectile burrowing through the ground along its heading. Like Rolling, it is
+// detonated by the CollisionSystem once finished.
+#[derive(Debug)]
+pub struct Digging {
+    heading: Vector2<f32>,
+    remaining: f32,
+    last_carve: Option<Point2<f32>>,
+    pub finished: bool,
+}
+
+impl Digging {
+    pub fn new(heading: Vector2<f32>, distance: f32) -> Digging {
+        Digging {
+            heading: heading,
+            remaining: distance,
+            last_carve: None,
+            finished: false,
+        }
+    }
+}
+
+impl specs::Component for Digging {
+    type Storage = specs::HashMapStorage<Digging>;
+}
+
+#[derive(Debug)]
+pub struct DiggingSystem;
+
+impl DiggingSystem {
+    pub fn new() -> DiggingSystem {
+        DiggingSystem {}
+    }
+}
+
+impl specs::System<Tick> for DiggingSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
+        let (mut digging, mut positions, projectiles, mut terrain, dim) = arg.fetch(|w| {
+            (w.write::<Digging>(),
+             w.write::<Position>(),
+             w.read::<Projectile>(),
+             w.write_resource::<Terrain>(),
+             w.read_resource::<Dimensions>())
+        });
+
+        for (d, projectile, p) in (&mut digging, &projectiles, &mut positions).join() {
+            if d.finished {
+                continue;
+            }
+            let step = (DIG_SPEED * tick.delta).min(d.remaining);
+            p.position += d.heading * step;
+            d.remaining -= step;
+
+            let x = p.position.x;
+            let done = d.remaining <= 0.0 || x < 0.0 || x > dim.game_width() as f32 ||
+                       p.position.y < 0.0;
+
+            // Carving in small steps would lose most of each step to rounding
+            let radius = projectile.weapon.size * TUNNEL_SCALE;
+            match d.last_carve {
+                None => terrain.carve(p.position, radius),
+                Some(last) if done || last.distance(p.position) >= radius / 2.0 => {
+                    terrain.carve_step(last, p.position, radius)
+                }
+                Some(_) => continue,
+            }
+            d.last_carve = Some(p.position);
+
+            if done {
+                debug!("Digger finished tunnelling at {:?}", p.position);
+                d.finished = true;
+            }
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use cgmath::prelude::*;
-use game::{ActivePlayer, Player};
+use game::{ActivePlayer, Command, CommandLog, Player, Players, Statistics, label_text,
+           log_command};
 use physics::*;
 use projectile::{Drawable, Projectile};
 use specs;
 use std::sync::mpsc;
-use tank::Tank;
+use tank::{Effect, Status, Tank};
+use text::Text;
 
 pub const POWER_MIN: f32 = 150.0;
 pub const POWER_SCALE: f32 = 100.0;
@@ -13,34 +15,46 @@ pub const POWER_SCALE: f32 = 100.0;
 pub struct FireControlSystem {
     player: Player,
     queue: mpsc::Receiver<()>,
+    log: Option<CommandLog>,
 }
 
 impl FireControlSystem {
-    pub fn new(player: Player) -> (FireControlSystem, mpsc::Sender<()>) {
+    pub fn new(player: Player, log: Option<CommandLog>) -> (FireControlSystem, mpsc::Sender<()>) {
         let (tx, rx) = mpsc::channel();
         (FireControlSystem {
              player: player,
              queue: rx,
+             log: log,
          },
          tx)
     }
 }
 
-impl<C> specs::System<C> for FireControlSystem {
-    fn run(&mut self, arg: specs::RunArg, _: C) {
-        let (tanks,
+impl specs::System<Tick> for FireControlSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
+        let (mut tanks,
+             mut statuses,
+             mut texts,
              mut projectiles,
              mut drawables,
              mut positions,
              mut velocities,
              mut mass,
+             mut drag,
+             mut stats,
+             players,
              firing) = arg.fetch(|w| {
-            (w.read::<Tank>(),
+            (w.write::<Tank>(),
+             w.write::<Status>(),
+             w.write::<Text>(),
              w.write::<Projectile>(),
              w.write::<Drawable>(),
              w.write::<Position>(),
              w.write::<Velocity>(),
              w.write::<Mass>(),
+             w.write::<Drag>(),
+             w.write_resource::<Statistics>(),
+             w.read_resource::<Players>(),
              w.read_resource::<ActivePlayer>())
         });
         let player = match firing.player() {
@@ -49,12 +63,13 @@ impl<C> specs::System<C> for FireControlSystem {
         };
         let mut inserted = false;
         while let Ok(()) = self.queue.try_recv() {
+            log_command(&self.log, tick.number, &self.player, Command::Fire);
             if inserted {
                 continue; // Eat up any remaining signals
             }
             let (eid, position) = {
                 // Can't insert into positions while the positions borrow is active:
-                let tank = match tanks.get(player.id()) {
+                let tank = match tanks.get_mut(player.id()) {
                     None => continue,
                     Some(t) => t,
                 };
@@ -62,20 +77,49 @@ impl<C> specs::System<C> for FireControlSystem {
                     None => continue,
                     Some(p) => p,
                 };
+                if statuses.get(player.id()).map_or(false, |s| s.has(Effect::Stunned)) {
+                    warn!("Tank {} is stunned and can't fire", player.player_number());
+                    continue;
+                }
+                let weapon = tank.weapon.weapon();
+                if !tank.arsenal.take(weapon.kind) {
+                    warn!("Tank {} has no {} shells left",
+                          player.player_number(),
+                          weapon.kind.name());
+                    continue;
+                }
+                // Switch away from a weapon once its last shell is gone
+                if !tank.arsenal.has(weapon.kind) {
+                    tank.weapon = tank.arsenal.cycle(weapon.kind, 1);
+                }
+                if let (Some((effect, turns)), Some(s)) = (weapon.shooter_effect,
+                                                          statuses.get_mut(player.id())) {
+                    debug!("Tank {} is {} for {} turns",
+                           player.player_number(),
+                           effect.name(),
+                           turns);
+                    s.apply(effect, turns, None);
+                }
+                if let Some(text) = texts.get_mut(player.id()) {
+                    text.text = label_text(players.name(&player), tank, statuses.get(player.id()));
+                }
                 let power = POWER_MIN + (POWER_SCALE * tank.power_level);
                 let vx = power * tank.barrel_orient.sin();
                 let vy = power * tank.barrel_orient.cos();
                 let velocity = Velocity::from([vx, vy]);
                 let position = Position::new(tank_pos.position.x, tank_pos.position.y,
-                    tank.barrel_orient, 7.0);
+                    tank.barrel_orient, weapon.size);
 
                 trace!("Angle: {:?}, Initial velocity: {:?}", tank.barrel_orient, velocity);
+                debug!("Tank {} fired a {}", player.player_number(), weapon.kind.name());
 
+                let shot = stats.shot_fired(&player);
                 let eid = arg.create_pure();
-                projectiles.insert(eid, Projectile::new());
+                projectiles.insert(eid, Projectile::new(weapon, Some(shot)));
                 drawables.insert(eid, Drawable::new());
                 velocities.insert(eid, velocity);
-                mass.insert(eid, Mass { mass: 75.0 });
+                mass.insert(eid, Mass { mass: weapon.mass });
+                drag.insert(eid, Drag { coefficient: weapon.drag });
                 (eid, position)
             }; // Borrow released here, now we can insert:
             positions.insert(eid, position);
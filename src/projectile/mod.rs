@@ -1,19 +1,42 @@
+use game::{Player, Shot};
 use specs;
 
 mod collision;
+mod dig;
 mod draw;
 mod firing;
+mod roll;
+mod split;
+mod weapon;
 
 pub use self::collision::CollisionSystem;
+pub use self::dig::{Digging, DiggingSystem};
 pub use self::draw::{Drawable, DrawSystem, PreDrawSystem};
 pub use self::firing::{FireControlSystem, POWER_MIN, POWER_SCALE};
+pub use self::roll::{Rolling, RollingSystem};
+pub use self::split::SplitSystem;
+pub use self::weapon::{Arsenal, Behaviour, Falloff, Weapon, WeaponKind};
 
 #[derive(Debug)]
-pub struct Projectile;
+pub struct Projectile {
+    pub weapon: Weapon,
+    // Who fired it, to credit them with the damage it does
+    pub shot: Option<Shot>,
+    // It can't hit its owner's tank until it has cleared that tank's hull
+    pub armed: bool,
+}
 
 impl Projectile {
-    pub fn new() -> Projectile {
-        Projectile {}
+    pub fn new(weapon: Weapon, shot: Option<Shot>) -> Projectile {
+        Projectile {
+            weapon: weapon,
+            shot: shot,
+            armed: false,
+        }
+    }
+
+    pub fn owner(&self) -> Option<Player> {
+        self.shot.map(|s| s.player)
     }
 }
 
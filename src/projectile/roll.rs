@@ -0,0 +1,117 @@
+use cgmath::Point2;
+use cgmath::prelude::*;
+use physics::*;
+use projectile::Projectile;
+use specs::{self, Join};
+use tank::Tank;
+use terrain::Terrain;
+
+const ROLL_FRICTION: f32 = 20.0;
+// Rollers that never find a valley explode eventually
+const MAX_ROLL_TIME: f32 = 8.0;
+
+// A projectile rolling along the ground. It is no longer moved by gravity and inertia,
+// and is detonated by the CollisionSystem once finished.
+#[derive(Debug)]
+pub struct Rolling {
+    // 1.0 when rolling towards increasing x, -1.0 otherwise
+    direction: f32,
+    speed: f32,
+    time: f32,
+    // Set once the roller is clear of the tank that fired it, which it can then roll back into
+    armed: bool,
+    pub finished: bool,
+}
+
+impl Rolling {
+    pub fn new(direction: f32, speed: f32) -> Rolling {
+        Rolling {
+            direction: direction,
+            speed: speed.max(0.0),
+            time: 0.0,
+            armed: false,
+            finished: false,
+        }
+    }
+}
+
+impl specs::Component for Rolling {
+    type Storage = specs::HashMapStorage<Rolling>;
+}
+
+#[derive(Debug)]
+pub struct RollingSystem;
+
+impl RollingSystem {
+    pub fn new() -> RollingSystem {
+        RollingSystem {}
+    }
+}
+
+impl specs::System<Tick> for RollingSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
+        let (mut rolling, mut positions, projectiles, tanks, entities, terrain, dim) =
+            arg.fetch(|w| {
+                (w.write::<Rolling>(),
+                 w.write::<Position>(),
+                 w.read::<Projectile>(),
+                 w.read::<Tank>(),
+                 w.entities(),
+                 w.read_resource::<Terrain>(),
+                 w.read_resource::<Dimensions>())
+            });
+
+        let targets: Vec<(specs::Entity, Point2<f32>, f32)> =
+            (&tanks.check(), &positions, &entities)
+                .join()
+                .map(|(_, p, e)| (e, p.position, p.scale))
+                .collect();
+        let max_x = dim.game_width() as f32 - 1.0;
+
+        for (r, projectile, p) in (&mut rolling, &projectiles, &mut positions).join() {
+            if r.finished {
+                continue;
+            }
+            r.time += tick.delta;
+
+            // The normal leans towards the downhill side
+            let slope = terrain.get_normal_dir(p.position.x);
+            let downhill = slope.sin();
+            if downhill * r.direction <= 0.0 {
+                debug!("Roller reached a valley at {:?}", p.position);
+                r.finished = true;
+                continue;
+            }
+
+            let acc = projectile.weapon.mass * -GRAVITY * downhill.abs() - ROLL_FRICTION;
+            r.speed += acc * tick.delta;
+            if r.speed <= 0.0 {
+                debug!("Roller stopped at {:?}", p.position);
+                r.finished = true;
+                continue;
+            }
+
+            let x = p.position.x + r.direction * r.speed * slope.cos() * tick.delta;
+            let x = x.max(0.0).min(max_x);
+            p.position = Point2::new(x, terrain.get_height(x));
+            p.orient = slope;
+
+            let touches = |t: Point2<f32>, scale: f32| t.distance(p.position) <= scale + p.scale;
+            let owner = projectile.owner().map(|o| o.id());
+            if !r.armed {
+                let on_owner = targets
+                    .iter()
+                    .any(|&(e, t, scale)| Some(e) == owner && touches(t, scale));
+                r.armed = !on_owner;
+            }
+            let ignored = if r.armed { None } else { owner };
+            let hit = targets
+                .iter()
+                .any(|&(e, t, scale)| Some(e) != ignored && touches(t, scale));
+            if hit || x <= 0.0 || x >= max_x || r.time > MAX_ROLL_TIME {
+                debug!("Roller finished rolling at {:?}", p.position);
+                r.finished = true;
+            }
+        }
+    }
+}
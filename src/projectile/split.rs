@@ -0,0 +1,73 @@
+use cgmath::{Deg, Vector2};
+use cgmath::prelude::*;
+use physics::*;
+use projectile::{Behaviour, Drawable, Projectile};
+use specs::{self, Join};
+
+#[derive(Debug)]
+pub struct SplitSystem;
+
+impl SplitSystem {
+    pub fn new() -> SplitSystem {
+        SplitSystem {}
+    }
+}
+
+impl specs::System<Tick> for SplitSystem {
+    fn run(&mut self, arg: specs::RunArg, _: Tick) {
+        let (mut projectiles,
+             mut drawables,
+             mut positions,
+             mut velocities,
+             mut masses,
+             mut drags,
+             entities) = arg.fetch(|w| {
+            (w.write::<Projectile>(),
+             w.write::<Drawable>(),
+             w.write::<Position>(),
+             w.write::<Velocity>(),
+             w.write::<Mass>(),
+             w.write::<Drag>(),
+             w.entities())
+        });
+
+        let mut warheads = Vec::new();
+        for (projectile, p, v, e) in (&projectiles, &positions, &velocities, &entities).join() {
+            let (count, spread, warhead) = match projectile.weapon.behaviour {
+                Behaviour::Split { warheads, spread, warhead } => (warheads, spread, warhead),
+                _ => continue,
+            };
+            // Split at the apex, once the shell starts falling
+            if v.velocity.y >= 0.0 {
+                continue;
+            }
+            arg.delete(e);
+
+            let speed = v.velocity.magnitude();
+            let heading: Deg<f32> = Deg::atan2(v.velocity.x, v.velocity.y);
+            debug!("Projectile split into {} {}s at {:?}",
+                   count,
+                   warhead.name(),
+                   p.position);
+            for i in 0..count {
+                let offset = if count > 1 {
+                    spread * (i as f32 / (count - 1) as f32 - 0.5)
+                } else {
+                    Deg::zero()
+                };
+                let angle = heading + offset;
+                let velocity = Vector2::new(speed * angle.sin(), speed * angle.cos());
+                warheads.push((p.position, angle, velocity, warhead.weapon(), projectile.shot));
+            }
+        }
+        for (position, angle, velocity, weapon, shot) in warheads {
+            let id = arg.create_pure();
+            projectiles.insert(id, Projectile::new(weapon, shot));
+            drawables.insert(id, Drawable::new());
+            positions.insert(id, Position::new(position.x, position.y, angle, weapon.size));
+            velocities.insert(id, Velocity::from(velocity));
+            masses.insert(id, Mass { mass: weapon.mass });
+            drags.insert(id, Drag { coefficient: weapon.drag });
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use cgmath::Deg;
+use tank::Effect;
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum WeaponKind {
+    BabyMissile,
+    Missile,
+    Nuke,
+    Mirv,
+    Roller,
+    Digger,
+}
+
+const ALL_WEAPONS: [WeaponKind; 6] = [WeaponKind::BabyMissile,
+                                      WeaponKind::Missile,
+                                      WeaponKind::Nuke,
+                                      WeaponKind::Mirv,
+                                      WeaponKind::Roller,
+                                      WeaponKind::Digger];
+
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum Behaviour {
+    // Explodes where it first hits the ground
+    Impact,
+    // Splits into warheads fanned out around its heading at the top of its flight
+    Split {
+        warheads: u32,
+        spread: Deg<f32>,
+        warhead: WeaponKind,
+    },
+    // Rolls downhill from where it lands, exploding in the valley
+    Roll,
+    // Tunnels on through the ground for the given distance, then explodes
+    Dig { distance: f32 },
+}
+
+// How an explosion's damage drops off from its centre to the edge of its reach
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Falloff {
+    // Full damage anywhere within reach
+    Flat,
+    Linear,
+    // Stays strong across most of the blast, dropping sharply near the edge
+    Quadratic,
+}
+
+impl Falloff {
+    // Fraction of the damage dealt at the given fraction of the way to the edge
+    pub fn scale(&self, distance: f32) -> f32 {
+        let distance = distance.max(0.0).min(1.0);
+        match *self {
+            Falloff::Flat => 1.0,
+            Falloff::Linear => 1.0 - distance,
+            Falloff::Quadratic => 1.0 - distance * distance,
+        }
+    }
+}
+
+// Everything that differs between shells, from flight to impact
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub struct Weapon {
+    pub kind: WeaponKind,
+    pub mass: f32,
+    pub explosion_radius: f32,
+    // Seconds the explosion lasts
+    pub explosion_duration: f32,
+    // Dealt to a tank at the centre of the explosion
+    pub damage: f32,
+    pub falloff: Falloff,
+    pub size: f32,
+    // Air drag, slowing the shell in proportion to its speed squared
+    pub drag: f32,
+    pub behaviour: Behaviour,
+    // Inflicted on every tank the explosion damages, for a number of turns
+    pub effect: Option<(Effect, u32)>,
+    // Given to the tank that fires it, for a number of its turns
+    pub shooter_effect: Option<(Effect, u32)>,
+}
+
+impl WeaponKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            WeaponKind::BabyMissile => "baby-missile",
+            WeaponKind::Missile => "missile",
+            WeaponKind::Nuke => "nuke",
+            WeaponKind::Mirv => "mirv",
+            WeaponKind::Roller => "roller",
+            WeaponKind::Digger => "digger",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WeaponKind> {
+        ALL_WEAPONS.iter().find(|w| w.name() == name).cloned()
+    }
+
+    pub fn weapon(&self) -> Weapon {
+        let (mass, explosion_radius, explosion_duration, damage, size, drag) = match *self {
+            WeaponKind::BabyMissile => (60.0, 30.0, 3.0, 30.0, 5.0, 0.020),
+            WeaponKind::Missile => (75.0, 50.0, 5.0, 55.0, 7.0, 0.015),
+            WeaponKind::Nuke => (120.0, 120.0, 7.0, 100.0, 12.0, 0.010),
+            WeaponKind::Mirv => (75.0, 40.0, 4.0, 40.0, 9.0, 0.015),
+            WeaponKind::Roller => (75.0, 45.0, 4.5, 50.0, 7.0, 0.015),
+            WeaponKind::Digger => (70.0, 40.0, 4.0, 50.0, 6.0, 0.012),
+        };
+        let behaviour = match *self {
+            WeaponKind::Mirv => {
+                Behaviour::Split {
+                    warheads: 5,
+                    spread: Deg(40.0),
+                    warhead: WeaponKind::BabyMissile,
+                }
+            }
+            WeaponKind::Roller => Behaviour::Roll,
+            WeaponKind::Digger => Behaviour::Dig { distance: 90.0 },
+            _ => Behaviour::Impact,
+        };
+        let falloff = match *self {
+            WeaponKind::Nuke => Falloff::Quadratic,
+            WeaponKind::BabyMissile => Falloff::Flat,
+            _ => Falloff::Linear,
+        };
+        let effect = match *self {
+            WeaponKind::Nuke => Some((Effect::Burning, 3)),
+            WeaponKind::Roller => Some((Effect::Jammed, 2)),
+            // Shakes the ground out from under whoever it goes off beneath
+            WeaponKind::Digger => Some((Effect::Stunned, 1)),
+            _ => None,
+        };
+        let shooter_effect = match *self {
+            // Light enough to fire while keeping the tank braced until the end of its next turn
+            WeaponKind::BabyMissile => Some((Effect::Shielded, 2)),
+            _ => None,
+        };
+        Weapon {
+            kind: *self,
+            mass: mass,
+            explosion_radius: explosion_radius,
+            explosion_duration: explosion_duration,
+            damage: damage,
+            falloff: falloff,
+            size: size,
+            drag: drag,
+            behaviour: behaviour,
+            effect: effect,
+            shooter_effect: shooter_effect,
+        }
+    }
+
+    // Cycles through the arsenal, wrapping around at either end
+    pub fn cycle(&self, steps: i32) -> WeaponKind {
+        let count = ALL_WEAPONS.len() as i32;
+        let index = ALL_WEAPONS.iter().position(|w| w == self).unwrap() as i32;
+        ALL_WEAPONS[(((index + steps) % count + count) % count) as usize]
+    }
+}
+
+// The shells a tank carries. Missiles never run out, everything else has to be bought.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Arsenal {
+    // Indexed like ALL_WEAPONS
+    shells: [u32; 6],
+}
+
+impl Arsenal {
+    pub fn new() -> Arsenal {
+        Arsenal { shells: [0; 6] }
+    }
+
+    fn index(kind: WeaponKind) -> usize {
+        ALL_WEAPONS.iter().position(|&w| w == kind).unwrap()
+    }
+
+    // None for unlimited shells
+    pub fn count(&self, kind: WeaponKind) -> Option<u32> {
+        if kind == WeaponKind::Missile {
+            None
+        } else {
+            Some(self.shells[Arsenal::index(kind)])
+        }
+    }
+
+    pub fn has(&self, kind: WeaponKind) -> bool {
+        self.count(kind).map_or(true, |n| n > 0)
+    }
+
+    pub fn add(&mut self, kind: WeaponKind, count: u32) {
+        self.shells[Arsenal::index(kind)] += count;
+    }
+
+    // Uses up a shell, returning false if there are none left
+    pub fn take(&mut self, kind: WeaponKind) -> bool {
+        if !self.has(kind) {
+            return false;
+        }
+        if kind != WeaponKind::Missile {
+            self.shells[Arsenal::index(kind)] -= 1;
+        }
+        true
+    }
+
+    // Like WeaponKind::cycle, skipping weapons without any shells left
+    pub fn cycle(&self, current: WeaponKind, steps: i32) -> WeaponKind {
+        let step = steps.signum();
+        let mut weapon = current;
+        for _ in 0..steps.abs() {
+            weapon = weapon.cycle(step);
+            while !self.has(weapon) {
+                weapon = weapon.cycle(step);
+            }
+        }
+        weapon
+    }
+
+    // Written as "<weapon>=<count>" for every weapon with shells
+    pub fn format(&self) -> Vec<String> {
+        ALL_WEAPONS
+            .iter()
+            .filter(|&&w| w != WeaponKind::Missile && self.has(w))
+            .map(|&w| format!("{}={}", w.name(), self.shells[Arsenal::index(w)]))
+            .collect()
+    }
+}
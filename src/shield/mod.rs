@@ -0,0 +1,101 @@
+use cgmath::{Point2, Vector2};
+use cgmath::prelude::*;
+use physics::Tick;
+use specs;
+
+mod draw;
+
+pub use self::draw::{Drawable, DrawSystem, PreDrawSystem};
+
+// A bubble around a tank that takes damage in its place until it runs out of strength
+#[derive(Debug)]
+pub struct Shield {
+    pub strength: f32,
+    max_strength: f32,
+    pub radius: f32,
+}
+
+impl Shield {
+    pub fn new(strength: f32, radius: f32) -> Shield {
+        Shield {
+            strength: strength,
+            max_strength: strength,
+            radius: radius,
+        }
+    }
+
+    // Returns the damage that gets through to the tank
+    pub fn absorb(&mut self, amount: f32) -> f32 {
+        let absorbed = amount.min(self.strength);
+        self.strength -= absorbed;
+        amount - absorbed
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.strength <= 0.0
+    }
+
+    // How much of its strength is left, from 0.0 to 1.0
+    pub fn remaining(&self) -> f32 {
+        if self.max_strength > 0.0 {
+            self.strength / self.max_strength
+        } else {
+            0.0
+        }
+    }
+
+    // The direction to bounce a shell at the given point away in, if it is heading into the
+    // bubble centred on the tank. Shells leaving the bubble, like those fired from inside it,
+    // pass straight through, as they do once the bubble is broken.
+    pub fn deflect(&self,
+                   center: Point2<f32>,
+                   point: Point2<f32>,
+                   velocity: Vector2<f32>)
+                   -> Option<Vector2<f32>> {
+        if self.is_broken() {
+            return None;
+        }
+        let offset = point - center;
+        if offset.magnitude() > self.radius || offset.magnitude() == 0.0 {
+            return None;
+        }
+        let normal = offset.normalize();
+        let inwards = velocity.dot(normal);
+        if inwards >= 0.0 {
+            return None;
+        }
+        Some(velocity - normal * (2.0 * inwards))
+    }
+}
+
+impl specs::Component for Shield {
+    type Storage = specs::HashMapStorage<Shield>;
+}
+
+// Takes away shields once they are broken, however they were worn down
+#[derive(Debug)]
+pub struct ShieldSystem;
+
+impl ShieldSystem {
+    pub fn new() -> ShieldSystem {
+        ShieldSystem {}
+    }
+}
+
+impl specs::System<Tick> for ShieldSystem {
+    fn run(&mut self, arg: specs::RunArg, _: Tick) {
+        use specs::Join;
+        let (mut shields, mut drawables, entities) =
+            arg.fetch(|w| (w.write::<Shield>(), w.write::<Drawable>(), w.entities()));
+        let broken: Vec<specs::Entity> = (&shields, &entities)
+            .join()
+            .filter(|&(s, _)| s.is_broken())
+            .map(|(_, e)| e)
+            .collect();
+        for e in broken {
+            info!("Shield broken!");
+            shields.remove(e);
+            drawables.remove(e);
+        }
+    }
+}
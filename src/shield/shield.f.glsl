@@ -0,0 +1,23 @@
+#version 330 core
+
+layout(std140) uniform;
+
+in vec2 mapping;
+
+uniform Locals {
+  mat4 transform;
+  float radius;
+  float strength;
+};
+
+out vec4 out_color;
+
+void main() {
+  float lensqr = dot(mapping, mapping);
+  if (lensqr > 1.0)
+    discard;
+
+  // Mostly clear in the middle, brighter towards the rim, fading as the shield weakens
+  float alpha = (0.1 + 0.4 * lensqr * lensqr) * (0.3 + 0.7 * strength);
+  out_color = vec4(0.4, 0.7, 1.0, alpha);
+}
@@ -1,11 +1,20 @@
-use cgmath::Deg;
+use cgmath::{Deg, Point2};
 use cgmath::prelude::*;
-use game::{ActivePlayer, Player};
+use game::{ActivePlayer, Command, CommandLog, Player, Players, label_position, label_text,
+           log_command};
+use physics::{Dimensions, Position, Tick};
 use specs;
 use std::sync::mpsc;
-use tank::Tank;
+use tank::{Effect, Status, Tank};
+use terrain::Terrain;
+use text::Text;
 
-#[derive(Debug)]
+// Distance covered per second while driving
+const MOVE_SPEED: f32 = 30.0;
+// Tanks can't drive up slopes any steeper than this
+const MAX_CLIMB: Deg<f32> = Deg(40.0);
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
 pub enum TankControl {
     AngleDecreasing,
     AngleIncreasing,
@@ -13,34 +22,99 @@ pub enum TankControl {
     PowerDecreasing,
     PowerIncreasing,
     PowerStop,
+    NextWeapon,
+    PreviousWeapon,
+    MoveLeft,
+    MoveRight,
+    MoveStop,
+}
+
+impl TankControl {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            TankControl::AngleDecreasing => "angle-decrease",
+            TankControl::AngleIncreasing => "angle-increase",
+            TankControl::AngleStop => "angle-stop",
+            TankControl::PowerDecreasing => "power-decrease",
+            TankControl::PowerIncreasing => "power-increase",
+            TankControl::PowerStop => "power-stop",
+            TankControl::NextWeapon => "weapon-next",
+            TankControl::PreviousWeapon => "weapon-previous",
+            TankControl::MoveLeft => "move-left",
+            TankControl::MoveRight => "move-right",
+            TankControl::MoveStop => "move-stop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TankControl> {
+        match name {
+            "angle-decrease" => Some(TankControl::AngleDecreasing),
+            "angle-increase" => Some(TankControl::AngleIncreasing),
+            "angle-stop" => Some(TankControl::AngleStop),
+            "power-decrease" => Some(TankControl::PowerDecreasing),
+            "power-increase" => Some(TankControl::PowerIncreasing),
+            "power-stop" => Some(TankControl::PowerStop),
+            "weapon-next" => Some(TankControl::NextWeapon),
+            "weapon-previous" => Some(TankControl::PreviousWeapon),
+            "move-left" => Some(TankControl::MoveLeft),
+            "move-right" => Some(TankControl::MoveRight),
+            "move-stop" => Some(TankControl::MoveStop),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct TankControlSystem {
     player: Player,
     queue: mpsc::Receiver<TankControl>,
+    log: Option<CommandLog>,
     angle_adjustment: Option<Deg<f32>>,
     power_adjustment: Option<f32>,
+    // -1.0 to drive left, 1.0 to drive right
+    movement: Option<f32>,
 }
 
 impl TankControlSystem {
-    pub fn new(player: Player) -> (TankControlSystem, mpsc::Sender<TankControl>) {
+    pub fn new(player: Player,
+               log: Option<CommandLog>)
+               -> (TankControlSystem, mpsc::Sender<TankControl>) {
         let (tx, rx) = mpsc::channel();
         (TankControlSystem {
              player: player,
              queue: rx,
+             log: log,
              angle_adjustment: None,
              power_adjustment: None,
+             movement: None,
          },
          tx)
     }
 }
 
-impl<C> specs::System<C> for TankControlSystem {
-    fn run(&mut self, arg: specs::RunArg, _: C) {
-        let (mut tanks, active) =
-            arg.fetch(|w| (w.write::<Tank>(), w.read_resource::<ActivePlayer>()));
+impl specs::System<Tick> for TankControlSystem {
+    fn run(&mut self, arg: specs::RunArg, tick: Tick) {
+        let (mut tanks,
+             statuses,
+             mut positions,
+             mut texts,
+             active,
+             players,
+             terrain,
+             dim) = arg.fetch(|w| {
+            (w.write::<Tank>(),
+             w.read::<Status>(),
+             w.write::<Position>(),
+             w.write::<Text>(),
+             w.read_resource::<ActivePlayer>(),
+             w.read_resource::<Players>(),
+             w.read_resource::<Terrain>(),
+             w.read_resource::<Dimensions>())
+        });
+        // Switching weapons only counts on our own turn
+        let mut weapon_steps = 0;
         while let Ok(control) = self.queue.try_recv() {
+            log_command(&self.log, tick.number, &self.player, Command::Tank(control));
             match control {
                 TankControl::AngleDecreasing => self.angle_adjustment = Some(Deg(-0.5)),
                 TankControl::AngleIncreasing => self.angle_adjustment = Some(Deg(0.5)),
@@ -48,6 +122,11 @@ impl<C> specs::System<C> for TankControlSystem {
                 TankControl::PowerDecreasing => self.power_adjustment = Some(-0.05),
                 TankControl::PowerIncreasing => self.power_adjustment = Some(0.05),
                 TankControl::PowerStop => self.power_adjustment = None,
+                TankControl::NextWeapon => weapon_steps += 1,
+                TankControl::PreviousWeapon => weapon_steps -= 1,
+                TankControl::MoveLeft => self.movement = Some(-1.0),
+                TankControl::MoveRight => self.movement = Some(1.0),
+                TankControl::MoveStop => self.movement = None,
             }
         }
         let player = match active.player() {
@@ -58,6 +137,11 @@ impl<C> specs::System<C> for TankControlSystem {
             None => return,
             Some(t) => t,
         };
+        let status = statuses.get(player.id());
+        // Jammed tanks keep their aim, weapon and place until it wears off
+        if status.map_or(false, |s| s.has(Effect::Jammed)) {
+            return;
+        }
         if let Some(angle) = self.angle_adjustment {
             tank.barrel_orient += angle;
             tank.barrel_orient.normalize();
@@ -72,6 +156,35 @@ impl<C> specs::System<C> for TankControlSystem {
                    player.player_number(),
                    tank.power_level);
         }
-
+        if let (Some(direction), Some(p)) = (self.movement, positions.get_mut(player.id())) {
+            let distance = (MOVE_SPEED * tick.delta).min(tank.fuel);
+            let x = (p.position.x + direction * distance)
+                .max(0.0)
+                .min(dim.game_width() as f32 - 1.0);
+            // The normal leans away from the uphill side
+            let climb = -terrain.get_normal_dir(x) * direction;
+            if distance <= 0.0 {
+                debug!("Tank {} is out of fuel", player.player_number());
+                self.movement = None;
+            } else if climb > MAX_CLIMB {
+                trace!("Tank {} can't climb {:?}", player.player_number(), climb);
+            } else {
+                tank.fuel -= (x - p.position.x).abs();
+                p.position = Point2::new(x, terrain.get_height(x));
+                p.orient = terrain.get_normal_dir(x);
+                if let Some(text) = texts.get_mut(player.id()) {
+                    text.screen_position = label_position(p.position);
+                }
+            }
+        }
+        if weapon_steps != 0 {
+            tank.weapon = tank.arsenal.cycle(tank.weapon, weapon_steps);
+            debug!("Tank {} switched to {}",
+                   player.player_number(),
+                   tank.weapon.name());
+            if let Some(text) = texts.get_mut(player.id()) {
+                text.text = label_text(players.name(&player), tank, status);
+            }
+        }
     }
 }
@@ -1,19 +1,32 @@
-use cgmath::{Deg, Matrix4, Vector3};
+use cgmath::{Deg, Matrix4, Point2, Vector3};
 use cgmath::prelude::*;
 use physics::Position;
+use projectile::{Arsenal, WeaponKind};
 use specs;
 
 mod control;
 mod draw;
+mod settle;
+mod status;
 
 pub use self::control::{TankControl, TankControlSystem};
 pub use self::draw::{Drawable, DrawSystem, PreDrawSystem};
+pub use self::settle::{Falling, SettlingSystem, is_unsupported};
+pub use self::status::{BURN_DAMAGE, Effect, Status};
+
+const FUEL: f32 = 100.0;
 
 #[derive(Debug)]
 pub struct Tank {
     pub barrel_orient: Deg<f32>,
     pub power_level: f32,
     pub health: f32,
+    pub weapon: WeaponKind,
+    pub arsenal: Arsenal,
+    // Each one lets the tank fall once without damage
+    pub parachutes: u32,
+    // How far the tank can still drive this match
+    pub fuel: f32,
 }
 
 impl Tank {
@@ -22,9 +35,20 @@ impl Tank {
             barrel_orient: Deg::zero(),
             power_level: 0.5,
             health: 100.0,
+            weapon: WeaponKind::Missile,
+            arsenal: Arsenal::new(),
+            parachutes: 0,
+            fuel: FUEL,
         }
     }
 
+    // Returns how much health was actually lost
+    pub fn take_damage(&mut self, amount: f32) -> f32 {
+        let lost = amount.min(self.health.max(0.0));
+        self.health -= amount;
+        lost
+    }
+
     pub fn barrel_to_world(&self, pos: &Position) -> Matrix4<f32> {
         Matrix4::from_translation(Vector3::new(pos.position.x, pos.position.y, 0.0)) *
         Matrix4::from_nonuniform_scale(pos.scale, pos.scale, 1.0) *
@@ -32,6 +56,17 @@ impl Tank {
     }
 }
 
+// Whether a point lies within the tank's hull, grown by a margin in world units.
+// The hull matches the body of the tank model: a trapezoid 2 wide at the base and 1.6 at the
+// top, 0.5 high, before being scaled and rotated into the world by the tank's position.
+pub fn hull_contains(pos: &Position, point: Point2<f32>, margin: f32) -> bool {
+    let d = (point - pos.position) / pos.scale;
+    let x = d.x * pos.orient.cos() - d.y * pos.orient.sin();
+    let y = d.x * pos.orient.sin() + d.y * pos.orient.cos();
+    let margin = margin / pos.scale;
+    y >= -margin && y <= 0.5 + margin && x.abs() <= 1.0 - 0.4 * y.max(0.0) + margin
+}
+
 impl specs::Component for Tank {
     type Storage = specs::HashMapStorage<Tank>;
 }
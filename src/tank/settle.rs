@@ -0,0 +1,112 @@
+use game::{Players, Statistics, label_position};
+use physics::*;
+use specs;
+use tank::Tank;
+use terrain::Terrain;
+use text::Text;
+
+const SETTLE_TOLERANCE: f32 = 1.0;
+const TANK_MASS: f32 = 100.0;
+const FALL_DAMAGE: f32 = 0.5;
+
+#[derive(Debug)]
+pub struct Falling {
+    start_height: f32,
+}
+
+impl specs::Component for Falling {
+    type Storage = specs::HashMapStorage<Falling>;
+}
+
+pub fn is_unsupported(pos: &Position, terrain: &Terrain) -> bool {
+    pos.position.y > terrain.get_height(pos.position.x) + SETTLE_TOLERANCE
+}
+
+#[derive(Debug)]
+pub struct SettlingSystem;
+
+impl SettlingSystem {
+    pub fn new() -> SettlingSystem {
+        SettlingSystem {}
+    }
+}
+
+impl<C> specs::System<C> for SettlingSystem {
+    fn run(&mut self, arg: specs::RunArg, _: C) {
+        use specs::Join;
+        let (mut tanks,
+             mut positions,
+             mut velocities,
+             mut masses,
+             mut falling,
+             mut texts,
+             players,
+             mut stats,
+             entities,
+             terrain) = arg.fetch(|w| {
+            (w.write::<Tank>(),
+             w.write::<Position>(),
+             w.write::<Velocity>(),
+             w.write::<Mass>(),
+             w.write::<Falling>(),
+             w.write::<Text>(),
+             w.read_resource::<Players>(),
+             w.write_resource::<Statistics>(),
+             w.entities(),
+             w.read_resource::<Terrain>())
+        });
+
+        let mut started = Vec::new();
+        let mut landed = Vec::new();
+        for (t, p, e) in (&mut tanks, &mut positions, &entities).join() {
+            match falling.get(e) {
+                None => {
+                    if is_unsupported(p, &terrain) {
+                        debug!("Tank {:?} lost the ground beneath it", e);
+                        started.push((e, p.position.y));
+                    }
+                }
+                Some(f) => {
+                    let ground = terrain.get_height(p.position.x);
+                    if p.position.y <= ground {
+                        p.position.y = ground;
+                        p.orient = terrain.get_normal_dir(p.position.x);
+                        let damage = if t.parachutes > 0 {
+                            t.parachutes -= 1;
+                            debug!("Tank {:?} landed safely by parachute", e);
+                            0.0
+                        } else {
+                            (f.start_height - ground) * FALL_DAMAGE
+                        };
+                        let dealt = t.take_damage(damage);
+                        debug!("Tank landed after falling {}, health: {}",
+                               f.start_height - ground,
+                               t.health);
+                        let destroyed = t.health <= 0.0;
+                        if destroyed {
+                            info!("Tank destroyed!");
+                            arg.delete(e);
+                        }
+                        if let Some(player) = players.by_tank(e) {
+                            stats.damage(&player, dealt, destroyed);
+                        }
+                        landed.push(e);
+                    }
+                }
+            }
+            if let Some(text) = texts.get_mut(e) {
+                text.screen_position = label_position(p.position);
+            }
+        }
+        for (e, height) in started {
+            falling.insert(e, Falling { start_height: height });
+            velocities.insert(e, Velocity::from([0.0, 0.0]));
+            masses.insert(e, Mass { mass: TANK_MASS });
+        }
+        for e in landed {
+            falling.remove(e);
+            velocities.remove(e);
+            masses.remove(e);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use game::Player;
+use specs;
+
+// Health a burning tank loses at the end of each of its turns
+pub const BURN_DAMAGE: f32 = 5.0;
+// Fraction of any damage that gets through to a shielded tank
+const SHIELDED_DAMAGE: f32 = 0.5;
+
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+pub enum Effect {
+    Burning,
+    // Misses its turns
+    Stunned,
+    // Takes less damage
+    Shielded,
+    // Can still fire, but can't aim, drive or switch weapons
+    Jammed,
+}
+
+impl Effect {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Effect::Burning => "burning",
+            Effect::Stunned => "stunned",
+            Effect::Shielded => "shielded",
+            Effect::Jammed => "jammed",
+        }
+    }
+
+    // Short enough to fit a few of them on a tank's label
+    fn tag(&self) -> &'static str {
+        match *self {
+            Effect::Burning => "fire",
+            Effect::Stunned => "stun",
+            Effect::Shielded => "shield",
+            Effect::Jammed => "jam",
+        }
+    }
+}
+
+#[derive(Debug,Copy,Clone)]
+struct Affliction {
+    effect: Effect,
+    // Turns of the tank's own left before it wears off
+    turns: u32,
+    // Who caused it, to credit them with any damage it does
+    source: Option<Player>,
+}
+
+// The effects a tank is under, which last for a number of its turns
+#[derive(Debug,Clone)]
+pub struct Status {
+    afflictions: Vec<Affliction>,
+}
+
+impl Status {
+    pub fn new() -> Status {
+        Status { afflictions: Vec::new() }
+    }
+
+    // Applying an effect the tank is already under only ever makes it last longer
+    pub fn apply(&mut self, effect: Effect, turns: u32, source: Option<Player>) {
+        if let Some(a) = self.afflictions.iter_mut().find(|a| a.effect == effect) {
+            a.turns = a.turns.max(turns);
+            a.source = source;
+            return;
+        }
+        self.afflictions.push(Affliction {
+                                  effect: effect,
+                                  turns: turns,
+                                  source: source,
+                              });
+    }
+
+    pub fn has(&self, effect: Effect) -> bool {
+        self.afflictions.iter().any(|a| a.effect == effect)
+    }
+
+    pub fn source(&self, effect: Effect) -> Option<Player> {
+        self.afflictions
+            .iter()
+            .find(|a| a.effect == effect)
+            .and_then(|a| a.source)
+    }
+
+    // How much of any damage taken actually gets through
+    pub fn damage_scale(&self) -> f32 {
+        if self.has(Effect::Shielded) {
+            SHIELDED_DAMAGE
+        } else {
+            1.0
+        }
+    }
+
+    // Counts down every effect at the end of the tank's turn, returning those that wore off
+    pub fn end_turn(&mut self) -> Vec<Effect> {
+        for a in &mut self.afflictions {
+            a.turns = a.turns.saturating_sub(1);
+        }
+        let expired = self.afflictions
+            .iter()
+            .filter(|a| a.turns == 0)
+            .map(|a| a.effect)
+            .collect();
+        self.afflictions.retain(|a| a.turns > 0);
+        expired
+    }
+
+    // Written as "<tag>:<turns>" for each effect, e.g. "fire:2 jam:1"
+    pub fn format(&self) -> String {
+        self.afflictions
+            .iter()
+            .map(|a| format!("{}:{}", a.effect.tag(), a.turns))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl specs::Component for Status {
+    type Storage = specs::HashMapStorage<Status>;
+}
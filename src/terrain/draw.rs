@@ -8,15 +8,25 @@ use terrain::Terrain;
 #[derive(Debug)]
 pub struct Drawable {
     bounds: Bounds,
+    vertices: Vec<Vertex>,
+    terrain_version: Option<u32>,
 }
 
 impl Drawable {
     pub fn new() -> Drawable {
-        Drawable { bounds: Bounds { transform: [[0.0; 4]; 4] } }
+        Drawable {
+            bounds: Bounds { transform: [[0.0; 4]; 4] },
+            vertices: Vec::new(),
+            terrain_version: None,
+        }
     }
 
-    pub fn update(&mut self, world_to_clip: &Matrix4<f32>) {
+    pub fn update(&mut self, world_to_clip: &Matrix4<f32>, terrain: &Terrain) {
         self.bounds.transform = (*world_to_clip).into();
+        if self.terrain_version != Some(terrain.version()) {
+            self.vertices = Vertex::generate(terrain);
+            self.terrain_version = Some(terrain.version());
+        }
     }
 }
 
@@ -64,6 +74,7 @@ const SHADER_FRAG: &'static [u8] = include_bytes!("terrain.f.glsl");
 
 pub struct DrawSystem<R: gfx::Resources> {
     bundle: gfx::pso::bundle::Bundle<R, pipe::Data<R>>,
+    uploaded_version: Option<u32>,
 }
 
 impl<R: gfx::Resources> DrawSystem<R> {
@@ -75,8 +86,6 @@ impl<R: gfx::Resources> DrawSystem<R> {
     {
         use gfx::traits::FactoryExt;
 
-        let vertices = Vertex::generate(terrain);
-
         let program = factory.link_program(SHADER_VERT, SHADER_FRAG).unwrap();
 
         let pso = factory
@@ -86,19 +95,35 @@ impl<R: gfx::Resources> DrawSystem<R> {
                                           pipe::new())
             .unwrap();
 
-        let (vbuf, slice) = factory.create_vertex_buffer_with_slice(&vertices[..], ());
+        // The heightmap changes as explosions carve into it, so the vertices are re-uploaded
+        let vbuf = factory
+            .create_buffer(terrain.heightmap.len() * 2,
+                           gfx::buffer::Role::Vertex,
+                           gfx::memory::Usage::Dynamic,
+                           gfx::Bind::empty())
+            .unwrap();
+        let slice = gfx::Slice::new_match_vertex_buffer(&vbuf);
         let data = pipe::Data {
             vbuf: vbuf,
             bounds: factory.create_constant_buffer(1),
             out: rtv,
         };
 
-        DrawSystem { bundle: gfx::Bundle::new(slice, pso, data) }
+        DrawSystem {
+            bundle: gfx::Bundle::new(slice, pso, data),
+            uploaded_version: None,
+        }
     }
 
-    pub fn draw<C>(&self, drawable: &Drawable, encoder: &mut gfx::Encoder<R, C>)
+    pub fn draw<C>(&mut self, drawable: &Drawable, encoder: &mut gfx::Encoder<R, C>)
         where C: gfx::CommandBuffer<R>
     {
+        if drawable.terrain_version.is_some() && drawable.terrain_version != self.uploaded_version {
+            encoder
+                .update_buffer(&self.bundle.data.vbuf, &drawable.vertices, 0)
+                .unwrap();
+            self.uploaded_version = drawable.terrain_version;
+        }
         encoder.update_constant_buffer(&self.bundle.data.bounds, &drawable.bounds);
         self.bundle.encode(encoder);
     }
@@ -116,13 +141,14 @@ impl PreDrawSystem {
 impl<C> specs::System<C> for PreDrawSystem {
     fn run(&mut self, arg: specs::RunArg, _: C) {
         use specs::Join;
-        let (mut terrain, dim) =
-            arg.fetch(|w| (w.write::<Drawable>(), w.read_resource::<Dimensions>()));
+        let (mut drawables, dim, terrain) = arg.fetch(|w| {
+            (w.write::<Drawable>(), w.read_resource::<Dimensions>(), w.read_resource::<Terrain>())
+        });
 
         let world_to_clip = dim.world_to_clip();
 
-        for t in (&mut terrain).join() {
-            t.update(&world_to_clip);
+        for t in (&mut drawables).join() {
+            t.update(&world_to_clip, &terrain);
         }
     }
 }
@@ -1,14 +1,14 @@
-use cgmath::Deg;
+use cgmath::{Deg, Point2};
 use cgmath::prelude::*;
-use rand::{self, Rng};
+use rand::Rng;
 use physics::Dimensions;
 
 mod draw;
 
 pub use self::draw::{Drawable, DrawSystem, PreDrawSystem};
 
-pub fn generate(dim: &Dimensions, points: usize) -> Terrain {
-    let noise = Noise::new(dim.game_width(), dim.game_height(), points);
+pub fn generate<R: Rng>(dim: &Dimensions, points: usize, rng: &mut R) -> Terrain {
+    let noise = Noise::new(dim.game_width(), dim.game_height(), points, rng);
 
     let mut hmap = Vec::with_capacity(dim.game_width() as usize);
 
@@ -19,16 +19,83 @@ pub fn generate(dim: &Dimensions, points: usize) -> Terrain {
     Terrain {
         max_height: dim.game_height(),
         heightmap: hmap,
+        version: 0,
     }
 }
 
+// The part of column x inside the circle, as (bottom, top)
+fn column_span(center: Point2<f32>, radius: f32, x: f32) -> Option<(f32, f32)> {
+    let dx = x - center.x;
+    if dx.abs() > radius {
+        return None;
+    }
+    let half_height = (radius * radius - dx * dx).sqrt();
+    Some((center.y - half_height, center.y + half_height))
+}
+
+// How much ground a column of the given height has between bottom and top
+fn solid_between(height: f32, bottom: f32, top: f32) -> f32 {
+    (top.min(height) - bottom.max(0.0)).max(0.0)
+}
+
 #[derive(Debug)]
 pub struct Terrain {
     pub max_height: u32,
     pub heightmap: Vec<u16>,
+    version: u32,
 }
 
 impl Terrain {
+    // Incremented every time the heightmap changes
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    // Removes the ground inside the circle. Any ground above the circle collapses into the gap.
+    pub fn carve(&mut self, center: Point2<f32>, radius: f32) {
+        self.carve_circle(center, radius, None);
+    }
+
+    // Like carve, but leaves out whatever the same circle at `previous` already removed,
+    // so a tunnel can be dug a step at a time without collapsing the ground twice.
+    pub fn carve_step(&mut self, previous: Point2<f32>, center: Point2<f32>, radius: f32) {
+        self.carve_circle(center, radius, Some(previous));
+    }
+
+    fn carve_circle(&mut self, center: Point2<f32>, radius: f32, exclude: Option<Point2<f32>>) {
+        if radius <= 0.0 || self.heightmap.is_empty() {
+            return;
+        }
+        let first = (center.x - radius).ceil().max(0.0) as usize;
+        let last = (center.x + radius).floor().min((self.heightmap.len() - 1) as f32);
+        if last < 0.0 {
+            return;
+        }
+        let mut changed = false;
+        for x in first..(last as usize + 1) {
+            let (bottom, top) = match column_span(center, radius, x as f32) {
+                Some(span) => span,
+                None => continue,
+            };
+            let height = self.heightmap[x] as f32;
+            let mut removed = solid_between(height, bottom, top);
+            if let Some((b, t)) = exclude.and_then(|p| column_span(p, radius, x as f32)) {
+                removed -= solid_between(height, bottom.max(b), top.min(t));
+            }
+            if removed <= 0.0 {
+                continue;
+            }
+            let carved = (height - removed).max(0.0).round() as u16;
+            if carved != self.heightmap[x] {
+                self.heightmap[x] = carved;
+                changed = true;
+            }
+        }
+        if changed {
+            self.version += 1;
+        }
+    }
+
     pub fn get_height(&self, x: f32) -> f32 {
         match x.floor() {
             x if x < 0.0 => self.heightmap[0] as f32,
@@ -63,10 +130,9 @@ struct Noise {
 }
 
 impl Noise {
-    fn new(width: u32, height: u32, count: usize) -> Noise {
+    fn new<R: Rng>(width: u32, height: u32, count: usize, rng: &mut R) -> Noise {
         let min = (height as f64) * 0.3;
         let max = (height as f64) * 0.7;
-        let mut rng = rand::thread_rng();
         let mut t = Vec::new();
         let mut p = Vec::new();
         let dx = width as f64 / count as f64;
@@ -62,7 +62,7 @@ gfx_defines!{
 
 const SHADER_VERT: &[u8] = include_bytes!("text.v.glsl");
 const SHADER_FRAG: &[u8] = include_bytes!("text.f.glsl");
-const MAX_VERTICES: usize = 64 * 6;
+const MAX_VERTICES: usize = super::MAX_LENGTH * 6;
 
 pub struct DrawSystem<R: gfx::Resources> {
     pso: gfx::pso::PipelineState<R, pipe::Meta>,
@@ -5,6 +5,9 @@ mod draw;
 
 pub use self::draw::{Drawable, DrawSystem, PreDrawSystem};
 
+// Longest text that fits the vertex buffer, at one quad per character
+pub const MAX_LENGTH: usize = 64;
+
 pub struct Text {
     pub text: String,
     pub screen_position: Point2<f32>,